@@ -1,11 +1,13 @@
 CREATE DATABASE IF NOT EXISTS acore_auth;
 CREATE DATABASE IF NOT EXISTS characters;
+CREATE DATABASE IF NOT EXISTS acore_world;
 
 -- Create user only if it doesn't exist (syntax depends on MySQL version, but this works on recent ones or we catch error)
 -- Safer to just grant and let it fail if user exists, or use CREATE USER IF NOT EXISTS
 CREATE USER IF NOT EXISTS 'wowuser'@'localhost' IDENTIFIED BY 'wowpassword';
 GRANT ALL PRIVILEGES ON acore_auth.* TO 'wowuser'@'localhost';
 GRANT ALL PRIVILEGES ON characters.* TO 'wowuser'@'localhost';
+GRANT ALL PRIVILEGES ON acore_world.* TO 'wowuser'@'localhost';
 FLUSH PRIVILEGES;
 
 USE acore_auth;
@@ -20,15 +22,32 @@ CREATE TABLE IF NOT EXISTS account (
 );
 
 CREATE TABLE IF NOT EXISTS realmlist (
-    id INT PRIMARY KEY,
+    id INT UNSIGNED PRIMARY KEY,
     name VARCHAR(32) NOT NULL,
-    address VARCHAR(255) NOT NULL,
-    port INT NOT NULL,
+    address VARCHAR(255) NOT NULL DEFAULT '127.0.0.1',
+    localAddress VARCHAR(255) NOT NULL DEFAULT '127.0.0.1',
+    localSubnetMask VARCHAR(255) NOT NULL DEFAULT '255.255.255.0',
+    port SMALLINT UNSIGNED NOT NULL DEFAULT 8085,
     icon TINYINT UNSIGNED NOT NULL DEFAULT 0,
-    color TINYINT UNSIGNED NOT NULL DEFAULT 2,
+    flag TINYINT UNSIGNED NOT NULL DEFAULT 2,
     timezone TINYINT UNSIGNED NOT NULL DEFAULT 0,
     allowedSecurityLevel TINYINT UNSIGNED NOT NULL DEFAULT 0,
-    population FLOAT NOT NULL DEFAULT 0
+    population FLOAT UNSIGNED NOT NULL DEFAULT 0,
+    gamebuild INT UNSIGNED NOT NULL DEFAULT 12340
+);
+
+CREATE TABLE IF NOT EXISTS motd (
+    realmid INT UNSIGNED PRIMARY KEY,
+    text LONGTEXT
+);
+
+CREATE TABLE IF NOT EXISTS uptime (
+    realmid INT UNSIGNED NOT NULL,
+    starttime INT UNSIGNED NOT NULL DEFAULT 0,
+    uptime INT UNSIGNED NOT NULL DEFAULT 0,
+    maxplayers SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    revision VARCHAR(255) NOT NULL DEFAULT 'AzerothCore',
+    PRIMARY KEY (realmid, starttime)
 );
 
 USE characters;
@@ -43,5 +62,196 @@ CREATE TABLE IF NOT EXISTS characters (
     level TINYINT UNSIGNED NOT NULL DEFAULT 1,
     xp INT UNSIGNED NOT NULL DEFAULT 0,
     money INT UNSIGNED NOT NULL DEFAULT 0,
-    online TINYINT UNSIGNED NOT NULL DEFAULT 0
+    totaltime INT UNSIGNED NOT NULL DEFAULT 0,
+    online TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    position_x FLOAT NOT NULL DEFAULT 0,
+    position_y FLOAT NOT NULL DEFAULT 0,
+    position_z FLOAT NOT NULL DEFAULT 0,
+    map SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    zone SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    at_login SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    activeTalentGroup TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    deleteInfos_Account INT UNSIGNED DEFAULT NULL,
+    deleteInfos_Name VARCHAR(12) DEFAULT NULL,
+    deleteDate INT UNSIGNED DEFAULT NULL
+);
+
+CREATE TABLE IF NOT EXISTS character_homebind (
+    guid INT UNSIGNED NOT NULL DEFAULT 0 PRIMARY KEY,
+    mapId SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    zoneId SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    posX FLOAT NOT NULL DEFAULT 0,
+    posY FLOAT NOT NULL DEFAULT 0,
+    posZ FLOAT NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS guild (
+    guildid INT UNSIGNED NOT NULL DEFAULT 0 PRIMARY KEY,
+    name VARCHAR(24) NOT NULL DEFAULT '',
+    leaderguid INT UNSIGNED NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS guild_member (
+    guildid INT UNSIGNED NOT NULL,
+    guid INT UNSIGNED NOT NULL PRIMARY KEY,
+    `rank` TINYINT UNSIGNED NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS arena_team (
+    arenaTeamId INT UNSIGNED NOT NULL DEFAULT 0 PRIMARY KEY,
+    name VARCHAR(24) NOT NULL,
+    captainGuid INT UNSIGNED NOT NULL DEFAULT 0,
+    type TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    rating SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    seasonGames SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    seasonWins SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    weekGames SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    weekWins SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    `rank` INT UNSIGNED NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS arena_team_member (
+    arenaTeamId INT UNSIGNED NOT NULL DEFAULT 0,
+    guid INT UNSIGNED NOT NULL DEFAULT 0,
+    weekGames SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    weekWins SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    seasonGames SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    seasonWins SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    personalRating SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (arenaTeamId, guid)
+);
+
+CREATE TABLE IF NOT EXISTS character_achievement (
+    guid INT UNSIGNED NOT NULL,
+    achievement SMALLINT UNSIGNED NOT NULL,
+    date INT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (guid, achievement)
+);
+
+CREATE TABLE IF NOT EXISTS character_achievement_progress (
+    guid INT UNSIGNED NOT NULL,
+    criteria SMALLINT UNSIGNED NOT NULL,
+    counter INT UNSIGNED NOT NULL,
+    date INT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (guid, criteria)
+);
+
+CREATE TABLE IF NOT EXISTS character_skills (
+    guid INT UNSIGNED NOT NULL,
+    skill SMALLINT UNSIGNED NOT NULL,
+    value SMALLINT UNSIGNED NOT NULL,
+    max SMALLINT UNSIGNED NOT NULL,
+    PRIMARY KEY (guid, skill)
+);
+
+CREATE TABLE IF NOT EXISTS item_instance (
+    guid INT UNSIGNED PRIMARY KEY,
+    itemEntry MEDIUMINT UNSIGNED NOT NULL DEFAULT 0,
+    owner_guid INT UNSIGNED NOT NULL DEFAULT 0,
+    count INT UNSIGNED NOT NULL DEFAULT 1
+);
+
+CREATE TABLE IF NOT EXISTS character_inventory (
+    guid INT UNSIGNED NOT NULL DEFAULT 0,
+    bag INT UNSIGNED NOT NULL DEFAULT 0,
+    slot TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    item INT UNSIGNED NOT NULL DEFAULT 0 PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS auctionhouse (
+    id INT UNSIGNED PRIMARY KEY,
+    houseid TINYINT UNSIGNED NOT NULL DEFAULT 7,
+    itemguid INT UNSIGNED NOT NULL DEFAULT 0,
+    itemowner INT UNSIGNED NOT NULL DEFAULT 0,
+    buyoutprice INT UNSIGNED NOT NULL DEFAULT 0,
+    time INT UNSIGNED NOT NULL DEFAULT 0,
+    buyguid INT UNSIGNED NOT NULL DEFAULT 0,
+    lastbid INT UNSIGNED NOT NULL DEFAULT 0,
+    startbid INT UNSIGNED NOT NULL DEFAULT 0,
+    deposit INT UNSIGNED NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS character_reputation (
+    guid INT UNSIGNED NOT NULL DEFAULT 0,
+    faction SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    standing INT NOT NULL DEFAULT 0,
+    flags SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (guid, faction)
+);
+
+CREATE TABLE IF NOT EXISTS character_queststatus (
+    guid INT UNSIGNED NOT NULL DEFAULT 0,
+    quest INT UNSIGNED NOT NULL DEFAULT 0,
+    status TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    explored TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    timer INT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (guid, quest)
+);
+
+CREATE TABLE IF NOT EXISTS character_queststatus_rewarded (
+    guid INT UNSIGNED NOT NULL DEFAULT 0,
+    quest INT UNSIGNED NOT NULL DEFAULT 0,
+    active TINYINT UNSIGNED NOT NULL DEFAULT 1,
+    PRIMARY KEY (guid, quest)
+);
+
+CREATE TABLE IF NOT EXISTS character_talent (
+    guid INT UNSIGNED NOT NULL,
+    spell MEDIUMINT UNSIGNED NOT NULL,
+    specMask TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (guid, spell)
+);
+
+CREATE TABLE IF NOT EXISTS character_glyphs (
+    guid INT UNSIGNED NOT NULL,
+    talentGroup TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    glyph1 SMALLINT UNSIGNED DEFAULT 0,
+    glyph2 SMALLINT UNSIGNED DEFAULT 0,
+    glyph3 SMALLINT UNSIGNED DEFAULT 0,
+    glyph4 SMALLINT UNSIGNED DEFAULT 0,
+    glyph5 SMALLINT UNSIGNED DEFAULT 0,
+    glyph6 SMALLINT UNSIGNED DEFAULT 0,
+    PRIMARY KEY (guid, talentGroup)
+);
+
+CREATE TABLE IF NOT EXISTS gm_ticket (
+    id INT UNSIGNED PRIMARY KEY,
+    type TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    playerGuid INT UNSIGNED NOT NULL DEFAULT 0,
+    name VARCHAR(12) NOT NULL,
+    description TEXT NOT NULL,
+    createTime INT UNSIGNED NOT NULL DEFAULT 0,
+    mapId SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    posX FLOAT NOT NULL DEFAULT 0,
+    posY FLOAT NOT NULL DEFAULT 0,
+    posZ FLOAT NOT NULL DEFAULT 0,
+    lastModifiedTime INT UNSIGNED NOT NULL DEFAULT 0,
+    closedBy INT NOT NULL DEFAULT 0,
+    assignedTo INT UNSIGNED NOT NULL DEFAULT 0,
+    comment TEXT NOT NULL,
+    response TEXT NOT NULL,
+    completed TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    escalated TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    viewed TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    needMoreHelp TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    resolvedBy INT NOT NULL DEFAULT 0
+);
+
+USE acore_world;
+
+CREATE TABLE IF NOT EXISTS item_template (
+    entry MEDIUMINT UNSIGNED PRIMARY KEY,
+    class TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    subclass TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    name VARCHAR(255) NOT NULL DEFAULT '',
+    Quality TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    InventoryType TINYINT UNSIGNED NOT NULL DEFAULT 0,
+    ItemLevel SMALLINT UNSIGNED NOT NULL DEFAULT 0,
+    RequiredLevel TINYINT UNSIGNED NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS quest_template (
+    ID MEDIUMINT UNSIGNED PRIMARY KEY,
+    QuestLevel SMALLINT NOT NULL DEFAULT 1,
+    LogTitle TEXT
 );
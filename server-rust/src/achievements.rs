@@ -0,0 +1,407 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use mongodb::{bson::doc, options::ReplaceOptions, Collection};
+use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder, Row};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path as FsPath;
+
+use crate::{
+    AppState,
+    clock::{interval_from_env, now_secs},
+    armory::find_character_guid,
+    dbc::DbcFile,
+    domain::{CharacterTraits, Locale},
+    models::{
+        AchievementCategorySummary, AchievementLeaderboard, AchievementLeaderboardEntry, AchievementProgress, AchievementScore,
+        CharacterAchievement, CharacterAchievements,
+    },
+    realms::RealmDb,
+};
+
+/// Field counts and indexes of the 3.3.5a tables read by `from_dbc`.
+const ACHIEVEMENT_FIELDS: usize = 62;
+const ACHIEVEMENT_CATEGORY_FIELDS: usize = 20;
+const ACHIEVEMENT_CRITERIA_FIELDS: usize = 31;
+/// enUS `Title_lang` in Achievement.dbc and `Name_lang` in Achievement_Category.dbc.
+const ACHIEVEMENT_NAME: usize = 4;
+const CATEGORY_NAME: usize = 2;
+
+/// Characters kept per realm in the cached leaderboard, the endpoint's
+/// maximum limit.
+const LEADERBOARD_SIZE: usize = 100;
+
+/// Achievement.dbc flags.
+const FLAG_COUNTER: u32 = 0x1;
+const FLAG_REALM_FIRST_REACH: u32 = 0x100;
+const FLAG_REALM_FIRST_KILL: u32 = 0x200;
+
+/// Static data for one achievement, from the client tables or the
+/// achievements data file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AchievementMeta {
+    pub id: u32,
+    pub name: String,
+    pub points: u32,
+    pub category: String,
+    #[serde(default)]
+    pub criteria: Vec<u32>,
+    #[serde(default, rename = "realmFirst")]
+    pub realm_first: bool,
+    #[serde(default)]
+    pub boss: bool,
+}
+
+/// Achievement metadata keyed by id. The characters DB only stores ids, so
+/// names, points and categories have to come from here.
+#[derive(Debug, Default)]
+pub struct AchievementCatalog {
+    entries: HashMap<u32, AchievementMeta>,
+    criteria: HashMap<u32, u32>,
+}
+
+impl AchievementCatalog {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let list: Vec<AchievementMeta> = serde_json::from_str(&raw).map_err(|e| format!("{}: {}", path, e))?;
+
+        let mut catalog = AchievementCatalog::default();
+        catalog.extend(list);
+        Ok(catalog)
+    }
+
+    /// Builds the catalog from the client's Achievement, Achievement_Category
+    /// and Achievement_Criteria tables. Statistics are left out; categories
+    /// are reported by their top-level name, as the game's summary does.
+    pub fn from_dbc(dir: &FsPath) -> Result<Self, String> {
+        // category id -> (parent, name); the root categories have parent -1.
+        let categories: HashMap<u32, (u32, String)> = DbcFile::open(dir, "Achievement_Category.dbc", ACHIEVEMENT_CATEGORY_FIELDS)?
+            .records()
+            .map(|record| (record.u32(0), (record.u32(1), record.string(CATEGORY_NAME).unwrap_or_default())))
+            .collect();
+        let top_level = |mut id: u32| {
+            // Bounded in case a malformed table has a parent cycle.
+            for _ in 0..categories.len() {
+                match categories.get(&id) {
+                    Some((parent, _)) if categories.contains_key(parent) => id = *parent,
+                    _ => break,
+                }
+            }
+            categories.get(&id).map(|(_, name)| name.clone()).unwrap_or_else(|| "Unknown".to_string())
+        };
+
+        let mut criteria: HashMap<u32, Vec<u32>> = HashMap::new();
+        for record in DbcFile::open(dir, "Achievement_Criteria.dbc", ACHIEVEMENT_CRITERIA_FIELDS)?.records() {
+            criteria.entry(record.u32(1)).or_default().push(record.u32(0));
+        }
+
+        let mut list = Vec::new();
+        for record in DbcFile::open(dir, "Achievement.dbc", ACHIEVEMENT_FIELDS)?.records() {
+            let flags = record.u32(41);
+            if flags & FLAG_COUNTER != 0 {
+                continue;
+            }
+            let id = record.u32(0);
+            list.push(AchievementMeta {
+                id,
+                name: record.string(ACHIEVEMENT_NAME).unwrap_or_default(),
+                points: record.u32(39),
+                category: top_level(record.u32(38)),
+                criteria: criteria.remove(&id).unwrap_or_default(),
+                realm_first: flags & (FLAG_REALM_FIRST_REACH | FLAG_REALM_FIRST_KILL) != 0,
+                boss: false,
+            });
+        }
+
+        let mut catalog = AchievementCatalog::default();
+        catalog.extend(list);
+        Ok(catalog)
+    }
+
+    /// Applies a data file on top of the client tables. The tables carry no
+    /// notion of raid bosses, so the file flags the hall of fame kills; entries
+    /// without criteria keep the ones read from the tables.
+    pub fn merge(&mut self, overrides: AchievementCatalog) {
+        let list = overrides.entries.into_values().map(|mut meta| {
+            if meta.criteria.is_empty() {
+                meta.criteria = self.get(meta.id).map(|m| m.criteria.clone()).unwrap_or_default();
+            }
+            meta
+        }).collect::<Vec<_>>();
+        self.extend(list);
+    }
+
+    fn extend(&mut self, list: Vec<AchievementMeta>) {
+        for meta in list {
+            for criteria_id in &meta.criteria {
+                self.criteria.insert(*criteria_id, meta.id);
+            }
+            self.entries.insert(meta.id, meta);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, id: u32) -> Option<&AchievementMeta> {
+        self.entries.get(&id)
+    }
+
+    pub fn points(&self, id: u32) -> u32 {
+        self.get(id).map(|m| m.points).unwrap_or(0)
+    }
+
+    /// Achievements whose first completion is recorded in the hall of fame.
+    pub fn hall_of_fame(&self) -> impl Iterator<Item = &AchievementMeta> {
+        self.entries.values().filter(|m| m.realm_first || m.boss)
+    }
+
+    pub fn achievement_for_criteria(&self, criteria_id: u32) -> Option<u32> {
+        self.criteria.get(&criteria_id).copied()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    pub limit: Option<usize>,
+}
+
+pub async fn get_character_achievements(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let guid = match find_character_guid(&realm.pool, &name).await {
+        Ok(Some(guid)) => guid,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Character not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to look up character {}: {}", name, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let completed_rows = match sqlx::query("SELECT achievement, date FROM character_achievement WHERE guid = ? ORDER BY date DESC")
+        .bind(guid)
+        .fetch_all(&realm.pool)
+        .await {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::error!("Failed to load achievements for {}: {}", name, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+
+    let progress_rows = match sqlx::query("SELECT criteria, counter, date FROM character_achievement_progress WHERE guid = ?")
+        .bind(guid)
+        .fetch_all(&realm.pool)
+        .await {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::error!("Failed to load achievement progress for {}: {}", name, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+
+    let catalog = &state.achievements;
+    let mut categories: BTreeMap<String, AchievementCategorySummary> = BTreeMap::new();
+    let mut total_points = 0;
+
+    let completed: Vec<CharacterAchievement> = completed_rows.iter().map(|row| {
+        let id = row.try_get::<u16, _>("achievement").unwrap_or_default() as u32;
+        let meta = catalog.get(id);
+        let points = meta.map(|m| m.points).unwrap_or(0);
+        let category = meta.map(|m| m.category.clone()).unwrap_or_else(|| "Unknown".to_string());
+
+        total_points += points;
+        let summary = categories.entry(category.clone()).or_insert_with(|| AchievementCategorySummary {
+            category: category.clone(),
+            completed: 0,
+            points: 0,
+        });
+        summary.completed += 1;
+        summary.points += points;
+
+        CharacterAchievement {
+            id,
+            name: meta.map(|m| m.name.clone()),
+            points,
+            category,
+            date: row.try_get::<u32, _>("date").unwrap_or_default() as i64,
+        }
+    }).collect();
+
+    let progress: Vec<AchievementProgress> = progress_rows.iter().map(|row| {
+        let criteria = row.try_get::<u16, _>("criteria").unwrap_or_default() as u32;
+        AchievementProgress {
+            criteria,
+            achievement: catalog.achievement_for_criteria(criteria),
+            counter: row.try_get::<u32, _>("counter").unwrap_or_default(),
+            date: row.try_get::<u32, _>("date").unwrap_or_default() as i64,
+        }
+    }).collect();
+
+    Json(CharacterAchievements {
+        character: name,
+        total_points,
+        categories: categories.into_values().collect(),
+        completed,
+        progress,
+    }).into_response()
+}
+
+fn leaderboard_ttl_secs() -> i64 {
+    std::env::var("ACHIEVEMENT_LEADERBOARD_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(900)
+}
+
+/// Starts the background task that ranks every realm's characters by
+/// achievement points, so the leaderboard never scans character_achievement
+/// on a request. `ACHIEVEMENT_LEADERBOARD_INTERVAL_SECS=0` disables it.
+pub fn spawn_leaderboard(state: AppState) {
+    let Some(period) = interval_from_env("ACHIEVEMENT_LEADERBOARD_INTERVAL_SECS", 600) else {
+        tracing::info!("Achievement leaderboard refresher disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            for (realm, pool) in state.realms.all() {
+                if let Err(e) = refresh_leaderboard(&state, realm, &pool).await {
+                    tracing::warn!("Achievement leaderboard refresh failed for realm {}: {}", realm, e);
+                }
+            }
+        }
+    });
+}
+
+/// Ranks the realm's characters and stores the top of the ranking. Points
+/// live in the catalog rather than the DB, so the totals are summed here.
+/// Deleted characters keep their achievements and are skipped.
+async fn refresh_leaderboard(
+    state: &AppState,
+    realm: u32,
+    pool: &sqlx::MySqlPool,
+) -> Result<AchievementLeaderboard, Box<dyn std::error::Error + Send + Sync>> {
+    let rows = sqlx::query(
+        "SELECT ca.guid, ca.achievement FROM character_achievement ca \
+         JOIN characters c ON c.guid = ca.guid \
+         WHERE c.deleteInfos_Account IS NULL",
+    )
+        .fetch_all(pool)
+        .await?;
+
+    let mut totals: HashMap<u32, (u32, u32)> = HashMap::new();
+    for row in &rows {
+        let guid = row.try_get::<u32, _>("guid").unwrap_or_default();
+        let achievement = row.try_get::<u16, _>("achievement").unwrap_or_default() as u32;
+        let entry = totals.entry(guid).or_insert((0, 0));
+        entry.0 += state.achievements.points(achievement);
+        entry.1 += 1;
+    }
+
+    let mut entries: Vec<AchievementScore> = totals.into_iter()
+        .map(|(guid, (points, completed))| AchievementScore { guid, points, completed })
+        .collect();
+    entries.sort_by(|a, b| b.points.cmp(&a.points).then(b.completed.cmp(&a.completed)).then(a.guid.cmp(&b.guid)));
+    entries.truncate(LEADERBOARD_SIZE);
+
+    let leaderboard = AchievementLeaderboard { realm, entries, updated_at: now_secs() };
+    let collection: Collection<AchievementLeaderboard> = state.mongo.collection("achievement_leaderboards");
+    collection.replace_one(
+        doc! { "_id": realm },
+        &leaderboard,
+        ReplaceOptions::builder().upsert(true).build(),
+    ).await?;
+    Ok(leaderboard)
+}
+
+/// The cached ranking, rebuilt when missing or older than
+/// `ACHIEVEMENT_LEADERBOARD_TTL_SECS`.
+async fn leaderboard(state: &AppState, realm: &RealmDb) -> Result<AchievementLeaderboard, Box<dyn std::error::Error + Send + Sync>> {
+    let collection: Collection<AchievementLeaderboard> = state.mongo.collection("achievement_leaderboards");
+    if let Some(cached) = collection.find_one(doc! { "_id": realm.id }, None).await? {
+        if now_secs() - cached.updated_at < leaderboard_ttl_secs() {
+            return Ok(cached);
+        }
+    }
+    refresh_leaderboard(state, realm.id, &realm.pool).await
+}
+
+pub async fn get_achievement_leaderboard(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Query(params): Query<LeaderboardQuery>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+    let limit = params.limit.unwrap_or(10).clamp(1, LEADERBOARD_SIZE);
+
+    let mut ranked = match leaderboard(&state, &realm).await {
+        Ok(leaderboard) => leaderboard.entries,
+        Err(e) => {
+            tracing::error!("Failed to load achievement leaderboard: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    ranked.truncate(limit);
+
+    if ranked.is_empty() {
+        return Json(Vec::<AchievementLeaderboardEntry>::new()).into_response();
+    }
+
+    // Characters deleted since the ranking was cached drop out here.
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT guid, name, race, class, gender, level FROM characters WHERE deleteInfos_Account IS NULL AND guid IN (",
+    );
+    let mut separated = builder.separated(", ");
+    for entry in &ranked {
+        separated.push_bind(entry.guid);
+    }
+    builder.push(")");
+
+    let characters = match builder.build().fetch_all(&realm.pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to load characters for leaderboard: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let by_guid: HashMap<u32, &sqlx::mysql::MySqlRow> = characters
+        .iter()
+        .map(|row| (row.try_get::<u32, _>("guid").unwrap_or_default(), row))
+        .collect();
+
+    let leaderboard: Vec<AchievementLeaderboardEntry> = ranked.iter()
+        .filter_map(|entry| {
+            let row = by_guid.get(&entry.guid)?;
+            Some(AchievementLeaderboardEntry {
+                name: row.try_get::<String, _>("name").unwrap_or_default(),
+                traits: CharacterTraits::new(
+                    row.try_get::<u8, _>("race").unwrap_or_default(),
+                    row.try_get::<u8, _>("class").unwrap_or_default(),
+                    row.try_get::<u8, _>("gender").ok(),
+                    locale,
+                ),
+                level: row.try_get::<u8, _>("level").unwrap_or_default(),
+                points: entry.points,
+                completed: entry.completed,
+            })
+        })
+        .collect();
+
+    Json(leaderboard).into_response()
+}
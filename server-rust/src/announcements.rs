@@ -0,0 +1,244 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    options::FindOptions,
+    Collection,
+};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    clock::now_secs,
+    handlers::authenticate,
+    models::{AnnouncementLog, NewsPost, User},
+    soap::SoapError,
+};
+
+/// Longest message the worldserver relays in a single chat packet.
+const MAX_MESSAGE_LEN: usize = 255;
+const MAX_MOTD_LEN: usize = 1024;
+
+/// The client splits the MOTD into lines on `@`, so newlines from the
+/// dashboard editor are converted.
+fn motd_text(motd: &str) -> String {
+    motd.trim().lines().map(str::trim_end).collect::<Vec<_>>().join("@")
+}
+
+pub fn validate_motd(motd: &str) -> Result<(), &'static str> {
+    if motd_text(motd).len() > MAX_MOTD_LEN {
+        return Err("MOTD must be at most 1024 characters");
+    }
+    Ok(())
+}
+
+/// Writes the MOTD to the auth `motd` table for every realm, read by each
+/// worldserver at startup, and sets it on the running server through SOAP. An
+/// empty MOTD clears it. Only the database write is required; a worldserver
+/// that is down, or that SOAP doesn't reach, picks the text up when it starts.
+/// Returns whether the running server was updated too.
+pub async fn push_motd(state: &AppState, motd: &str) -> Result<bool, sqlx::Error> {
+    let text = motd_text(motd);
+    let mut realms: Vec<u32> = sqlx::query_scalar("SELECT id FROM realmlist")
+        .fetch_all(&state.mysql_auth)
+        .await?;
+    if !realms.contains(&state.realms.default_id()) {
+        realms.push(state.realms.default_id());
+    }
+
+    let mut tx = state.mysql_auth.begin().await?;
+    for realm in &realms {
+        sqlx::query("INSERT INTO motd (realmid, text) VALUES (?, ?) ON DUPLICATE KEY UPDATE text = VALUES(text)")
+            .bind(realm)
+            .bind(&text)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    // SOAP reaches the default realm's worldserver only. The realm id goes
+    // first so a MOTD that starts with a number isn't read as one.
+    match state.soap.execute(&format!("server set motd {} {}", state.realms.default_id(), text)).await {
+        Ok(_) => Ok(true),
+        Err(SoapError::Disabled) => Ok(false),
+        Err(e) => {
+            tracing::warn!("Failed to set MOTD on the worldserver: {}", e);
+            Ok(false)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnnouncementRequest {
+    pub message: String,
+    /// announce (default), notify or both.
+    pub kind: Option<String>,
+    /// Also publish the message on the dashboard news feed.
+    #[serde(rename = "postToNews", default)]
+    pub post_to_news: bool,
+    /// News title; defaults to "Announcement".
+    pub title: Option<String>,
+}
+
+/// Console commands for an announcement kind: `announce` goes to chat,
+/// `notify` shows on screen.
+fn announcement_commands(kind: &str) -> Option<&'static [&'static str]> {
+    match kind {
+        "announce" => Some(&["announce"]),
+        "notify" => Some(&["notify"]),
+        "both" => Some(&["announce", "notify"]),
+        _ => None,
+    }
+}
+
+async fn author_name(state: &AppState, user_id: &str) -> String {
+    let users: Collection<User> = state.mongo.collection("users");
+    let user = match ObjectId::parse_str(user_id) {
+        Ok(oid) => users.find_one(doc! { "_id": oid }, None).await.ok().flatten(),
+        Err(_) => None,
+    };
+    user.map(|u| u.nickname).unwrap_or_else(|| "Staff".to_string())
+}
+
+async fn record_announcement(state: &AppState, entry: AnnouncementLog) {
+    let logs: Collection<AnnouncementLog> = state.mongo.collection("announcements");
+    if let Err(e) = logs.insert_one(entry, None).await {
+        tracing::error!("Failed to record announcement: {}", e);
+    }
+}
+
+pub async fn create_announcement(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<AnnouncementRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let message = payload.message.trim();
+    if message.is_empty() || message.len() > MAX_MESSAGE_LEN || message.contains(['\r', '\n']) {
+        return (StatusCode::BAD_REQUEST, "Message must be a single line of 1-255 characters").into_response();
+    }
+    let kind = payload.kind.as_deref().unwrap_or("announce");
+    let Some(commands) = announcement_commands(kind) else {
+        return (StatusCode::BAD_REQUEST, "Kind must be announce, notify or both").into_response();
+    };
+
+    for (sent, command) in commands.iter().enumerate() {
+        if let Err(e) = state.soap.execute(&format!("{} {}", command, message)).await {
+            tracing::warn!("Failed to broadcast announcement: {}", e);
+            // Players already saw the commands before this one, so the
+            // broadcast is recorded with the part that failed.
+            if sent > 0 {
+                record_announcement(&state, AnnouncementLog {
+                    id: None,
+                    message: message.to_string(),
+                    kind: kind.to_string(),
+                    news_id: None,
+                    requested_by: claims.sub,
+                    at: now_secs(),
+                    failed: Some(command.to_string()),
+                }).await;
+            }
+            return e.into_response();
+        }
+    }
+    tracing::info!("Admin {} broadcast {}: {}", claims.sub, kind, message);
+
+    let mut news_id = None;
+    if payload.post_to_news {
+        let title = payload.title.as_deref().map(str::trim).filter(|t| !t.is_empty()).unwrap_or("Announcement");
+        let post = NewsPost {
+            id: None,
+            title: title.to_string(),
+            content: message.to_string(),
+            author: author_name(&state, &claims.sub).await,
+            created_at: now_secs(),
+        };
+        let news: Collection<NewsPost> = state.mongo.collection("news");
+        match news.insert_one(post, None).await {
+            Ok(result) => news_id = result.inserted_id.as_object_id().map(|id| id.to_hex()),
+            Err(e) => tracing::error!("Failed to post announcement to news: {}", e),
+        }
+    }
+
+    let entry = AnnouncementLog {
+        id: None,
+        message: message.to_string(),
+        kind: kind.to_string(),
+        news_id: news_id.clone(),
+        requested_by: claims.sub,
+        at: now_secs(),
+        failed: None,
+    };
+    record_announcement(&state, entry).await;
+
+    Json(serde_json::json!({
+        "message": message,
+        "kind": kind,
+        "postedToNews": news_id.is_some(),
+        "newsId": news_id,
+    })).into_response()
+}
+
+/// Dashboard news feed, newest first.
+pub async fn list_news(State(state): State<AppState>) -> impl IntoResponse {
+    let news: Collection<NewsPost> = state.mongo.collection("news");
+    let cursor = match news.find(doc! {}, FindOptions::builder().sort(doc! { "createdAt": -1 }).limit(50).build()).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    match cursor.try_collect::<Vec<NewsPost>>().await {
+        Ok(posts) => {
+            let posts: Vec<serde_json::Value> = posts.into_iter().map(|p| serde_json::json!({
+                "id": p.id.map(|id| id.to_hex()).unwrap_or_default(),
+                "title": p.title,
+                "content": p.content,
+                "author": p.author,
+                "date": chrono::DateTime::from_timestamp(p.created_at, 0)
+                    .map(|d| d.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default(),
+            })).collect();
+            Json(posts).into_response()
+        },
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn motd_lines_are_joined_for_the_client() {
+        assert_eq!(motd_text("  Welcome!\r\nDouble XP this weekend.  \n"), "Welcome!@Double XP this weekend.");
+        assert_eq!(motd_text("One line"), "One line");
+        assert_eq!(motd_text("   "), "");
+    }
+
+    #[test]
+    fn validate_motd_counts_the_text_sent_to_the_client() {
+        assert!(validate_motd(&"x".repeat(MAX_MOTD_LEN)).is_ok());
+        assert!(validate_motd(&"x".repeat(MAX_MOTD_LEN + 1)).is_err());
+        // Surrounding whitespace is trimmed before the length check.
+        assert!(validate_motd(&format!("  {}  ", "x".repeat(MAX_MOTD_LEN))).is_ok());
+    }
+
+    #[test]
+    fn announcement_kinds_map_to_console_commands() {
+        assert_eq!(announcement_commands("both"), Some(&["announce", "notify"][..]));
+        assert_eq!(announcement_commands("notify"), Some(&["notify"][..]));
+        assert_eq!(announcement_commands("whisper"), None);
+    }
+}
@@ -0,0 +1,188 @@
+use axum::{
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder, Row};
+use std::collections::HashMap;
+
+use crate::{realms::RealmDb, domain::{CharacterTraits, Faction, Locale, Race}, models::{ArenaTeam, ArenaTeamMember}};
+
+#[derive(Debug, Deserialize)]
+pub struct LadderQuery {
+    #[serde(rename = "type")]
+    pub bracket: Option<u8>,
+    pub faction: Option<String>,
+    pub class: Option<u8>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+fn team_from_row(row: &sqlx::mysql::MySqlRow, locale: Locale) -> ArenaTeam {
+    let faction = row.try_get::<u8, _>("captainRace").ok().and_then(Race::from_id).map(Race::faction);
+    ArenaTeam {
+        id: row.try_get::<u32, _>("arenaTeamId").unwrap_or_default(),
+        name: row.try_get::<String, _>("name").unwrap_or_default(),
+        bracket: row.try_get::<u8, _>("type").unwrap_or_default(),
+        rating: row.try_get::<u16, _>("rating").unwrap_or_default(),
+        rank: row.try_get::<u32, _>("rank").unwrap_or_default(),
+        season_games: row.try_get::<u16, _>("seasonGames").unwrap_or_default(),
+        season_wins: row.try_get::<u16, _>("seasonWins").unwrap_or_default(),
+        week_games: row.try_get::<u16, _>("weekGames").unwrap_or_default(),
+        week_wins: row.try_get::<u16, _>("weekWins").unwrap_or_default(),
+        captain: row.try_get::<Option<String>, _>("captainName").unwrap_or_default(),
+        faction: faction.map(|f| f.slug().to_string()),
+        faction_name: faction.map(|f| f.name(locale).to_string()),
+        members: Vec::new(),
+    }
+}
+
+fn member_from_row(row: &sqlx::mysql::MySqlRow, locale: Locale) -> ArenaTeamMember {
+    ArenaTeamMember {
+        guid: row.try_get::<u32, _>("guid").unwrap_or_default(),
+        name: row.try_get::<String, _>("name").unwrap_or_default(),
+        traits: CharacterTraits::new(
+            row.try_get::<u8, _>("race").unwrap_or_default(),
+            row.try_get::<u8, _>("class").unwrap_or_default(),
+            row.try_get::<u8, _>("gender").ok(),
+            locale,
+        ),
+        level: row.try_get::<u8, _>("level").unwrap_or_default(),
+        personal_rating: row.try_get::<u16, _>("personalRating").unwrap_or_default(),
+        season_games: row.try_get::<u16, _>("seasonGames").unwrap_or_default(),
+        season_wins: row.try_get::<u16, _>("seasonWins").unwrap_or_default(),
+        week_games: row.try_get::<u16, _>("weekGames").unwrap_or_default(),
+        week_wins: row.try_get::<u16, _>("weekWins").unwrap_or_default(),
+    }
+}
+
+const TEAM_COLUMNS: &str = "SELECT t.arenaTeamId, t.name, t.type, t.rating, t.rank, \
+    t.seasonGames, t.seasonWins, t.weekGames, t.weekWins, \
+    c.name AS captainName, c.race AS captainRace \
+    FROM arena_team t LEFT JOIN characters c ON c.guid = t.captainGuid";
+
+const MEMBER_COLUMNS: &str = "SELECT m.arenaTeamId, m.guid, m.personalRating, \
+    m.seasonGames, m.seasonWins, m.weekGames, m.weekWins, \
+    c.name, c.race, c.class, c.gender, c.level \
+    FROM arena_team_member m JOIN characters c ON c.guid = m.guid";
+
+async fn fetch_members(
+    pool: &sqlx::MySqlPool,
+    team_ids: &[u32],
+    locale: Locale,
+) -> Result<HashMap<u32, Vec<ArenaTeamMember>>, sqlx::Error> {
+    let mut members: HashMap<u32, Vec<ArenaTeamMember>> = HashMap::new();
+    if team_ids.is_empty() {
+        return Ok(members);
+    }
+
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(MEMBER_COLUMNS);
+    builder.push(" WHERE m.arenaTeamId IN (");
+    let mut separated = builder.separated(", ");
+    for id in team_ids {
+        separated.push_bind(*id);
+    }
+    builder.push(") ORDER BY m.personalRating DESC");
+
+    for row in builder.build().fetch_all(pool).await? {
+        let team_id = row.try_get::<u32, _>("arenaTeamId").unwrap_or_default();
+        members.entry(team_id).or_default().push(member_from_row(&row, locale));
+    }
+
+    Ok(members)
+}
+
+pub async fn get_ladder(
+    realm: RealmDb,
+    headers: HeaderMap,
+    Query(params): Query<LadderQuery>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+    let bracket = params.bracket.unwrap_or(2);
+    if ![2, 3, 5].contains(&bracket) {
+        return (StatusCode::BAD_REQUEST, "Arena type must be 2, 3 or 5").into_response();
+    }
+
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
+    let offset = params.offset.unwrap_or(0);
+
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(TEAM_COLUMNS);
+    builder.push(" WHERE t.type = ").push_bind(bracket);
+
+    if let Some(faction) = &params.faction {
+        match Faction::from_slug(faction) {
+            Some(f) => { builder.push(format!(" AND c.race IN ({})", f.race_ids_sql())); },
+            None => return (StatusCode::BAD_REQUEST, "Faction must be alliance or horde").into_response(),
+        }
+    }
+
+    if let Some(class) = params.class {
+        builder
+            .push(" AND EXISTS (SELECT 1 FROM arena_team_member fm JOIN characters fc ON fc.guid = fm.guid WHERE fm.arenaTeamId = t.arenaTeamId AND fc.class = ")
+            .push_bind(class)
+            .push(")");
+    }
+
+    builder
+        .push(" ORDER BY t.rating DESC, t.seasonWins DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    let rows = match builder.build().fetch_all(&realm.pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to load arena ladder: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let mut teams: Vec<ArenaTeam> = rows.iter().map(|row| team_from_row(row, locale)).collect();
+    let team_ids: Vec<u32> = teams.iter().map(|t| t.id).collect();
+
+    let mut members = match fetch_members(&realm.pool, &team_ids, locale).await {
+        Ok(m) => m,
+        Err(e) => {
+            tracing::error!("Failed to load arena team members: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    for team in teams.iter_mut() {
+        team.members = members.remove(&team.id).unwrap_or_default();
+    }
+
+    Json(teams).into_response()
+}
+
+pub async fn get_team(
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(team_id): Path<u32>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+    let query = format!("{} WHERE t.arenaTeamId = ?", TEAM_COLUMNS);
+    let mut team = match sqlx::query(&query)
+        .bind(team_id)
+        .fetch_optional(&realm.pool)
+        .await {
+            Ok(Some(row)) => team_from_row(&row, locale),
+            Ok(None) => return (StatusCode::NOT_FOUND, "Arena team not found").into_response(),
+            Err(e) => {
+                tracing::error!("Failed to load arena team {}: {}", team_id, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+
+    match fetch_members(&realm.pool, &[team_id], locale).await {
+        Ok(mut members) => team.members = members.remove(&team_id).unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("Failed to load members of arena team {}: {}", team_id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    }
+
+    Json(team).into_response()
+}
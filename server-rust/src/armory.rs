@@ -0,0 +1,126 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use sqlx::Row;
+
+use crate::{
+    AppState,
+    domain::{CharacterTraits, Locale},
+    gear::character_gear,
+    models::ArmoryCharacter,
+    professions::character_professions,
+    quests::character_quests,
+    realms::RealmDb,
+    reputation::character_reputations,
+    talents::character_talents,
+};
+
+pub async fn find_character_guid(pool: &sqlx::MySqlPool, name: &str) -> Result<Option<u32>, sqlx::Error> {
+    sqlx::query_scalar("SELECT guid FROM characters WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn get_character(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+    let query = "SELECT c.guid, c.name, c.race, c.class, c.gender, c.level, c.xp, c.money, c.totaltime, c.online, c.activeTalentGroup, g.name AS guildName \
+        FROM characters c \
+        LEFT JOIN guild_member gm ON gm.guid = c.guid \
+        LEFT JOIN guild g ON g.guildid = gm.guildid \
+        WHERE c.name = ?";
+
+    let row = match sqlx::query(query)
+        .bind(&name)
+        .fetch_optional(&realm.pool)
+        .await {
+            Ok(Some(row)) => row,
+            Ok(None) => return (StatusCode::NOT_FOUND, "Character not found").into_response(),
+            Err(e) => {
+                tracing::error!("Failed to load armory character {}: {}", name, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+
+    let guid = row.try_get::<u32, _>("guid").unwrap_or_default();
+
+    let achievement_ids: Vec<u16> = match sqlx::query_scalar("SELECT achievement FROM character_achievement WHERE guid = ?")
+        .bind(guid)
+        .fetch_all(&realm.pool)
+        .await {
+            Ok(ids) => ids,
+            Err(e) => {
+                tracing::error!("Failed to load achievements for {}: {}", name, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+
+    let professions = match character_professions(&realm.pool, guid).await {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Failed to load professions for {}: {}", name, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let race = row.try_get::<u8, _>("race").unwrap_or_default();
+    let class = row.try_get::<u8, _>("class").unwrap_or_default();
+    let reputations = match character_reputations(&realm.pool, &state.factions, guid, race, class, locale).await {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!("Failed to load reputations for {}: {}", name, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let quests = match character_quests(&realm.pool, &state.mysql_world, guid).await {
+        Ok(q) => q,
+        Err(e) => {
+            tracing::error!("Failed to load quests for {}: {}", name, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let active_group = row.try_get::<u8, _>("activeTalentGroup").unwrap_or_default();
+    let talents = match character_talents(&realm.pool, &state.talents, guid, class, active_group).await {
+        Ok(t) => t,
+        Err(e) => {
+            tracing::error!("Failed to load talents for {}: {}", name, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let gear = character_gear(&state, &realm, guid).await;
+
+    Json(ArmoryCharacter {
+        guid,
+        name: row.try_get::<String, _>("name").unwrap_or_default(),
+        traits: CharacterTraits::new(
+            race,
+            class,
+            row.try_get::<u8, _>("gender").ok(),
+            locale,
+        ),
+        level: row.try_get::<u8, _>("level").unwrap_or_default(),
+        xp: row.try_get::<u32, _>("xp").unwrap_or_default(),
+        money: row.try_get::<u32, _>("money").unwrap_or_default(),
+        total_time: row.try_get::<u32, _>("totaltime").unwrap_or_default(),
+        online: row.try_get::<u8, _>("online").unwrap_or_default() != 0,
+        guild: row.try_get::<Option<String>, _>("guildName").unwrap_or_default(),
+        achievement_points: achievement_ids.iter().map(|id| state.achievements.points(*id as u32)).sum(),
+        professions,
+        reputations,
+        quests,
+        talents,
+        average_item_level: gear.as_ref().map(|g| g.average_item_level),
+        gear_score: gear.as_ref().map(|g| g.gear_score),
+    }).into_response()
+}
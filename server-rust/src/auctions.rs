@@ -0,0 +1,442 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::{AggregateOptions, FindOptions},
+    Collection, IndexModel,
+};
+use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder, Row};
+use std::collections::HashMap;
+
+use crate::{
+    AppState,
+    clock::{interval_from_env, now_secs},
+    domain::{ItemQuality, Locale, Money},
+    items::{search_templates, templates_by_entry},
+    models::{AuctionItem, AuctionListing, AuctionPriceSample, EconomySample},
+    realms::RealmDb,
+    retention,
+};
+
+/// AuctionHouse.dbc ids as stored in `auctionhouse.houseid`: (id, slug).
+const AUCTION_HOUSES: &[(u8, &str)] = &[(2, "alliance"), (6, "horde"), (7, "neutral")];
+
+#[derive(Debug, Deserialize)]
+pub struct AuctionQuery {
+    pub name: Option<String>,
+    pub quality: Option<String>,
+    #[serde(rename = "minLevel")]
+    pub min_level: Option<u8>,
+    #[serde(rename = "maxLevel")]
+    pub max_level: Option<u8>,
+    pub house: Option<String>,
+    pub sort: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PriceHistoryQuery {
+    pub days: Option<i64>,
+}
+
+fn house_slug(id: u8) -> &'static str {
+    AUCTION_HOUSES.iter().find(|(i, _)| *i == id).map(|(_, s)| *s).unwrap_or("neutral")
+}
+
+/// Distinct item entries currently up for auction, optionally in one house.
+async fn listed_entries(pool: &sqlx::MySqlPool, house: Option<u8>) -> Result<Vec<u32>, sqlx::Error> {
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT DISTINCT ii.itemEntry FROM auctionhouse a JOIN item_instance ii ON ii.guid = a.itemguid WHERE 1 = 1",
+    );
+    if let Some(house) = house {
+        builder.push(" AND a.houseid = ").push_bind(house);
+    }
+    builder.build_query_scalar::<u32>().fetch_all(pool).await
+}
+
+pub async fn search_auctions(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Query(params): Query<AuctionQuery>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+
+    let house = match params.house.as_deref() {
+        Some(slug) => match AUCTION_HOUSES.iter().find(|(_, s)| *s == slug) {
+            Some((id, _)) => Some(*id),
+            None => return (StatusCode::BAD_REQUEST, "House must be alliance, horde or neutral").into_response(),
+        },
+        None => None,
+    };
+    let quality = match params.quality.as_deref() {
+        Some(q) => match ItemQuality::parse(q) {
+            Some(q) => Some(q),
+            None => return (StatusCode::BAD_REQUEST, "Unknown item quality").into_response(),
+        },
+        None => None,
+    };
+    let order = match params.sort.as_deref().unwrap_or("buyout") {
+        "buyout" => "a.buyoutprice = 0, a.buyoutprice ASC",
+        "bid" => "GREATEST(a.lastbid, a.startbid) ASC",
+        "expires" => "a.time ASC",
+        _ => return (StatusCode::BAD_REQUEST, "Sort must be buyout, bid or expires").into_response(),
+    };
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+    let offset = params.offset.unwrap_or(0);
+
+    let name = params.name.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    let filtered = name.is_some() || quality.is_some() || params.min_level.is_some() || params.max_level.is_some();
+
+    // item_template lives in the world database, so item filters are applied
+    // to the templates of the items currently listed and the matching entries
+    // restrict the auction query from there.
+    let mut templates = HashMap::new();
+    if filtered {
+        let entries = match listed_entries(&realm.pool, house).await {
+            Ok(e) => e,
+            Err(e) => {
+                tracing::error!("Failed to load listed items: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+        templates = match search_templates(&state.mysql_world, &entries, name, quality, params.min_level, params.max_level).await {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::error!("Failed to search item templates: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+        if templates.is_empty() {
+            return Json(Vec::<AuctionListing>::new()).into_response();
+        }
+    }
+
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT a.id, a.houseid, a.buyoutprice, a.time, a.lastbid, a.startbid, ii.itemEntry, ii.count, c.name AS seller \
+         FROM auctionhouse a \
+         JOIN item_instance ii ON ii.guid = a.itemguid \
+         LEFT JOIN characters c ON c.guid = a.itemowner \
+         WHERE 1 = 1",
+    );
+    if let Some(house) = house {
+        builder.push(" AND a.houseid = ").push_bind(house);
+    }
+    if filtered {
+        builder.push(" AND ii.itemEntry IN (");
+        let mut separated = builder.separated(", ");
+        for entry in templates.keys() {
+            separated.push_bind(*entry);
+        }
+        builder.push(")");
+    }
+    builder.push(format!(" ORDER BY {}, a.id ASC LIMIT ", order))
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    let rows = match builder.build().fetch_all(&realm.pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to load auctions: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    if !filtered {
+        let entries: Vec<u32> = rows.iter().map(|r| r.try_get::<u32, _>("itemEntry").unwrap_or_default()).collect();
+        templates = match templates_by_entry(&state.mysql_world, &entries).await {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::error!("Failed to load item templates: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+    }
+
+    let listings: Vec<AuctionListing> = rows.iter().map(|row| {
+        let entry = row.try_get::<u32, _>("itemEntry").unwrap_or_default();
+        let template = templates.get(&entry);
+        let quality = template.map(|t| t.quality).unwrap_or_default();
+        let last_bid = row.try_get::<u32, _>("lastbid").unwrap_or_default();
+        let buyout = row.try_get::<u32, _>("buyoutprice").unwrap_or_default();
+
+        AuctionListing {
+            id: row.try_get::<u32, _>("id").unwrap_or_default(),
+            house: house_slug(row.try_get::<u8, _>("houseid").unwrap_or_default()).to_string(),
+            item: AuctionItem {
+                entry,
+                name: template.map(|t| t.name.clone()).unwrap_or_default(),
+                quality,
+                quality_name: ItemQuality::from_id(quality).unwrap_or(ItemQuality::Common).name(locale).to_string(),
+                item_level: template.map(|t| t.item_level).unwrap_or_default(),
+                required_level: template.map(|t| t.required_level).unwrap_or_default(),
+                count: row.try_get::<u32, _>("count").unwrap_or(1),
+            },
+            seller: row.try_get::<Option<String>, _>("seller").unwrap_or_default(),
+            start_bid: Money::from_copper(row.try_get::<u32, _>("startbid").unwrap_or_default() as u64),
+            current_bid: (last_bid > 0).then(|| Money::from_copper(last_bid as u64)),
+            buyout: (buyout > 0).then(|| Money::from_copper(buyout as u64)),
+            expires_at: row.try_get::<u32, _>("time").unwrap_or_default() as i64,
+        }
+    }).collect();
+
+    Json(listings).into_response()
+}
+
+fn median(sorted: &[i64]) -> i64 {
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Starts the background task that records per-item auction prices and the
+/// money supply of every realm. Samples are kept for
+/// `AUCTION_SAMPLE_RETENTION_DAYS` (365 by default, the longest price history
+/// served). `AUCTION_SAMPLE_INTERVAL_SECS=0` disables it.
+pub fn spawn_price_sampler(state: AppState) {
+    let Some(period) = interval_from_env("AUCTION_SAMPLE_INTERVAL_SECS", 3600) else {
+        tracing::info!("Auction price sampling disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let prices: Collection<AuctionPriceSample> = state.mongo.collection("auction_price_samples");
+        let index = IndexModel::builder().keys(doc! { "realm": 1, "itemEntry": 1, "at": 1 }).build();
+        if let Err(e) = prices.create_index(index, None).await {
+            tracing::warn!("Failed to create auction_price_samples index: {}", e);
+        }
+        let retention_days = retention::retention_days("AUCTION_SAMPLE_RETENTION_DAYS", 365);
+        if let Err(e) = retention::ensure_ttl(&prices, retention_days).await {
+            tracing::warn!("Failed to set up auction_price_samples retention: {}", e);
+        }
+        let economy: Collection<EconomySample> = state.mongo.collection("economy_samples");
+        if let Err(e) = retention::ensure_ttl(&economy, retention_days).await {
+            tracing::warn!("Failed to set up economy_samples retention: {}", e);
+        }
+
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            for (realm, pool) in state.realms.all() {
+                match sample_prices(&state, realm, &pool, retention_days).await {
+                    Ok(count) => tracing::info!("Recorded auction prices for {} items on realm {}", count, realm),
+                    Err(e) => tracing::warn!("Auction price sampling failed for realm {}: {}", realm, e),
+                }
+            }
+        }
+    });
+}
+
+async fn sample_prices(state: &AppState, realm: u32, pool: &sqlx::MySqlPool, retention_days: i64) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    // Bid-only auctions have no asking price, so only buyouts are sampled.
+    let rows = sqlx::query(
+        "SELECT ii.itemEntry, ii.count, a.buyoutprice \
+         FROM auctionhouse a JOIN item_instance ii ON ii.guid = a.itemguid \
+         WHERE a.buyoutprice > 0",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let money_supply: i64 = sqlx::query_scalar("SELECT CAST(COALESCE(SUM(money), 0) AS SIGNED) FROM characters WHERE account <> 0")
+        .fetch_one(pool)
+        .await?;
+
+    let mut per_item: HashMap<u32, (Vec<i64>, u32)> = HashMap::new();
+    for row in &rows {
+        let entry = row.try_get::<u32, _>("itemEntry").unwrap_or_default();
+        let count = row.try_get::<u32, _>("count").unwrap_or(1).max(1);
+        let buyout = row.try_get::<u32, _>("buyoutprice").unwrap_or_default() as i64;
+        let item = per_item.entry(entry).or_insert_with(|| (Vec::new(), 0));
+        item.0.push(buyout / count as i64);
+        item.1 += count;
+    }
+
+    let at = now_secs();
+    let samples: Vec<AuctionPriceSample> = per_item.into_iter().map(|(entry, (mut prices, quantity))| {
+        prices.sort_unstable();
+        AuctionPriceSample {
+            id: None,
+            realm,
+            item_entry: entry,
+            at,
+            min: prices[0],
+            median: median(&prices),
+            listings: prices.len() as u32,
+            quantity,
+            expires_at: retention::expires_at(at, retention_days),
+        }
+    }).collect();
+
+    let count = samples.len();
+    if !samples.is_empty() {
+        let prices: Collection<AuctionPriceSample> = state.mongo.collection("auction_price_samples");
+        prices.insert_many(samples, None).await?;
+    }
+
+    let economy: Collection<EconomySample> = state.mongo.collection("economy_samples");
+    economy.insert_one(EconomySample {
+        id: None,
+        realm,
+        at,
+        money_supply,
+        listings: rows.len() as u32,
+        items_sampled: count as u32,
+        expires_at: retention::expires_at(at, retention_days),
+    }, None).await?;
+
+    Ok(count)
+}
+
+pub async fn get_item_price_history(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(entry): Path<u32>,
+    Query(params): Query<PriceHistoryQuery>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+    let days = params.days.unwrap_or(30).clamp(1, 365);
+    let since = now_secs() - days * 24 * 3600;
+
+    let template = match templates_by_entry(&state.mysql_world, &[entry]).await {
+        Ok(mut t) => match t.remove(&entry) {
+            Some(t) => t,
+            None => return (StatusCode::NOT_FOUND, "Item not found").into_response(),
+        },
+        Err(e) => {
+            tracing::error!("Failed to load item template {}: {}", entry, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let collection: Collection<AuctionPriceSample> = state.mongo.collection("auction_price_samples");
+    let cursor = match collection.find(
+        doc! { "realm": realm.id, "itemEntry": entry, "at": { "$gte": since } },
+        FindOptions::builder().sort(doc! { "at": 1 }).build(),
+    ).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+    let samples: Vec<AuctionPriceSample> = match cursor.try_collect().await {
+        Ok(s) => s,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    let series: Vec<serde_json::Value> = samples.iter().map(|s| {
+        serde_json::json!({
+            "at": s.at,
+            "min": Money::from_copper(s.min as u64),
+            "median": Money::from_copper(s.median as u64),
+            "listings": s.listings,
+            "quantity": s.quantity,
+        })
+    }).collect();
+
+    Json(serde_json::json!({
+        "entry": entry,
+        "name": template.name,
+        "quality": template.quality,
+        "qualityName": ItemQuality::from_id(template.quality).unwrap_or(ItemQuality::Common).name(locale),
+        "days": days,
+        "series": series,
+    })).into_response()
+}
+
+/// Price index over the window: for every sampling run, the geometric mean of
+/// each item's median relative to its first median in the window, times 100.
+/// Shown next to the money supply so inflation is visible from both sides.
+pub async fn get_economy_index(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    Query(params): Query<PriceHistoryQuery>,
+) -> impl IntoResponse {
+    let days = params.days.unwrap_or(30).clamp(1, 90);
+    let since = now_secs() - days * 24 * 3600;
+
+    // Each item's first median in the window is its baseline; the mean of the
+    // log ratios per run is grouped in Mongo rather than loading every sample.
+    let prices: Collection<AuctionPriceSample> = state.mongo.collection("auction_price_samples");
+    let pipeline = vec![
+        doc! { "$match": { "realm": realm.id, "at": { "$gte": since }, "median": { "$gt": 0 } } },
+        doc! { "$sort": { "at": 1 } },
+        doc! { "$group": {
+            "_id": "$itemEntry",
+            "baseline": { "$first": "$median" },
+            "samples": { "$push": { "at": "$at", "median": "$median" } },
+        } },
+        doc! { "$unwind": "$samples" },
+        doc! { "$group": {
+            "_id": "$samples.at",
+            "meanLogRatio": { "$avg": { "$ln": { "$divide": ["$samples.median", "$baseline"] } } },
+        } },
+    ];
+    let cursor = match prices.aggregate(pipeline, AggregateOptions::builder().allow_disk_use(true).build()).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+    let runs: Vec<Document> = match cursor.try_collect().await {
+        Ok(r) => r,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+    let indexes: HashMap<i64, f64> = runs.iter()
+        .filter_map(|run| Some((run.get_i64("_id").ok()?, run.get_f64("meanLogRatio").ok()?)))
+        .map(|(at, mean)| (at, (mean.exp() * 10_000.0).round() / 100.0))
+        .collect();
+
+    let economy: Collection<EconomySample> = state.mongo.collection("economy_samples");
+    let cursor = match economy.find(
+        doc! { "realm": realm.id, "at": { "$gte": since } },
+        FindOptions::builder().sort(doc! { "at": 1 }).build(),
+    ).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+    let economy_runs: Vec<EconomySample> = match cursor.try_collect().await {
+        Ok(s) => s,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    let series: Vec<serde_json::Value> = economy_runs.iter().map(|run| {
+        serde_json::json!({
+            "at": run.at,
+            "priceIndex": indexes.get(&run.at),
+            "moneySupply": Money::from_copper(run.money_supply.max(0) as u64),
+            "listings": run.listings,
+            "itemsSampled": run.items_sampled,
+        })
+    }).collect();
+
+    Json(serde_json::json!({
+        "days": days,
+        "series": series,
+    })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_of_odd_count_is_the_middle_value() {
+        assert_eq!(median(&[5]), 5);
+        assert_eq!(median(&[1, 3, 100]), 3);
+    }
+
+    #[test]
+    fn median_of_even_count_averages_the_middle_pair() {
+        assert_eq!(median(&[1, 3]), 2);
+        // Rounded down to whole copper.
+        assert_eq!(median(&[10, 20, 25, 40]), 22);
+    }
+}
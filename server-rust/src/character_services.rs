@@ -0,0 +1,815 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{bson::{doc, Document}, options::{FindOptions, UpdateOptions}, Collection};
+use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder, Row};
+
+use crate::{
+    AppState,
+    clock::now_secs,
+    handlers::{authenticate, caller_game_accounts, is_duplicate_key, Claims},
+    domain::{CharacterTraits, Locale},
+    models::{CharacterServiceLog, CharacterTransferLog, DeletedCharacter, UnstuckLog},
+    points,
+    realms::RealmDb,
+};
+
+fn unstuck_cooldown_secs() -> i64 {
+    std::env::var("UNSTUCK_COOLDOWN_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600)
+}
+
+pub(crate) struct OwnedCharacter {
+    pub(crate) guid: u32,
+    pub(crate) account: u32,
+    pub(crate) online: bool,
+    pub(crate) at_login: u16,
+}
+
+/// Loads a character by name and checks that it belongs to one of the caller's
+/// game accounts. Admins pass the check when `allow_admin` is set.
+pub(crate) async fn load_owned_character(
+    pool: &sqlx::MySqlPool,
+    claims: &Claims,
+    accounts: &[u32],
+    name: &str,
+    allow_admin: bool,
+) -> Result<OwnedCharacter, (StatusCode, &'static str)> {
+    let row = match sqlx::query("SELECT guid, account, online, at_login FROM characters WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await {
+            Ok(Some(row)) => row,
+            Ok(None) => return Err((StatusCode::NOT_FOUND, "Character not found")),
+            Err(e) => {
+                tracing::error!("Failed to load character {}: {}", name, e);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error"));
+            }
+        };
+
+    let account = row.try_get::<u32, _>("account").unwrap_or_default();
+    let is_admin = allow_admin && claims.role == "admin";
+    if !is_admin && !accounts.contains(&account) {
+        return Err((StatusCode::FORBIDDEN, "You do not own this character"));
+    }
+
+    Ok(OwnedCharacter {
+        guid: row.try_get::<u32, _>("guid").unwrap_or_default(),
+        account,
+        online: row.try_get::<u8, _>("online").unwrap_or_default() != 0,
+        at_login: row.try_get::<u16, _>("at_login").unwrap_or_default(),
+    })
+}
+
+/// Claims the cooldown window `key` in the `cooldowns` collection. The claim
+/// is one conditional upsert that only matches an expired window; a live one
+/// makes the insert fail on `_id`, so concurrent requests can't both pass.
+/// Returns the seconds left when still on cooldown.
+async fn claim_cooldown(state: &AppState, key: &str, now: i64, secs: i64) -> Result<Option<i64>, mongodb::error::Error> {
+    let cooldowns: Collection<Document> = state.mongo.collection("cooldowns");
+    match cooldowns.update_one(
+        doc! { "_id": key, "until": { "$lte": now } },
+        doc! { "$set": { "until": now + secs, "claimedAt": now } },
+        UpdateOptions::builder().upsert(true).build(),
+    ).await {
+        Ok(_) => Ok(None),
+        Err(e) if is_duplicate_key(&e) => {
+            let until = cooldowns.find_one(doc! { "_id": key }, None).await?
+                .and_then(|d| d.get_i64("until").ok())
+                .unwrap_or(now);
+            Ok(Some((until - now).max(1)))
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// Gives back a window claimed at `now` whose request then failed.
+async fn release_cooldown(state: &AppState, key: &str, now: i64) {
+    let cooldowns: Collection<Document> = state.mongo.collection("cooldowns");
+    if let Err(e) = cooldowns.delete_one(doc! { "_id": key, "claimedAt": now }, None).await {
+        tracing::error!("Failed to release cooldown {}: {}", key, e);
+    }
+}
+
+/// Moves a logged out character to its hearthstone location, returning
+/// (map, zone, x, y, z).
+async fn move_to_homebind(
+    pool: &sqlx::MySqlPool,
+    guid: u32,
+    name: &str,
+) -> Result<(u16, u16, f32, f32, f32), axum::response::Response> {
+    let home = match sqlx::query("SELECT mapId, zoneId, posX, posY, posZ FROM character_homebind WHERE guid = ?")
+        .bind(guid)
+        .fetch_optional(pool)
+        .await {
+            Ok(Some(row)) => row,
+            Ok(None) => return Err((StatusCode::NOT_FOUND, "Character has no hearthstone location").into_response()),
+            Err(e) => {
+                tracing::error!("Failed to load homebind for {}: {}", name, e);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response());
+            }
+        };
+
+    let map = home.try_get::<u16, _>("mapId").unwrap_or_default();
+    let zone = home.try_get::<u16, _>("zoneId").unwrap_or_default();
+    let x = home.try_get::<f32, _>("posX").unwrap_or_default();
+    let y = home.try_get::<f32, _>("posY").unwrap_or_default();
+    let z = home.try_get::<f32, _>("posZ").unwrap_or_default();
+
+    // Re-check `online` in the UPDATE itself so a login between the checks above
+    // and this write can't be teleported under the player's feet.
+    let moved = match sqlx::query("UPDATE characters SET position_x = ?, position_y = ?, position_z = ?, map = ?, zone = ? WHERE guid = ? AND online = 0")
+        .bind(x)
+        .bind(y)
+        .bind(z)
+        .bind(map)
+        .bind(zone)
+        .bind(guid)
+        .execute(pool)
+        .await {
+            Ok(result) => result.rows_affected(),
+            Err(e) => {
+                tracing::error!("Failed to move character {}: {}", name, e);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response());
+            }
+        };
+
+    if moved == 0 {
+        return Err((StatusCode::CONFLICT, "Character must be logged out first").into_response());
+    }
+    Ok((map, zone, x, y, z))
+}
+
+pub async fn unstuck_character(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    let accounts = match caller_game_accounts(&state, &claims).await {
+        Ok(a) => a,
+        Err(e) => return e.into_response(),
+    };
+
+    let character = match load_owned_character(&realm.pool, &claims, &accounts, &name, false).await {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    let guid = character.guid;
+    if character.online {
+        return (StatusCode::CONFLICT, "Character must be logged out first").into_response();
+    }
+
+    let now = now_secs();
+    let cooldown_key = format!("unstuck:{}:{}", realm.id, guid);
+    match claim_cooldown(&state, &cooldown_key, now, unstuck_cooldown_secs()).await {
+        Ok(None) => {},
+        Ok(Some(remaining)) => return (
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Unstuck is on cooldown for another {} seconds", remaining),
+        ).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+
+    let (map, zone, x, y, z) = match move_to_homebind(&realm.pool, guid, &name).await {
+        Ok(position) => position,
+        Err(response) => {
+            release_cooldown(&state, &cooldown_key, now).await;
+            return response;
+        }
+    };
+
+    let logs: Collection<UnstuckLog> = state.mongo.collection("unstuck_log");
+    let entry = UnstuckLog {
+        id: None,
+        realm: realm.id,
+        guid,
+        name: name.clone(),
+        requested_by: claims.sub,
+        at: now,
+    };
+    if let Err(e) = logs.insert_one(entry, None).await {
+        tracing::error!("Failed to record unstuck for {}: {}", name, e);
+    }
+
+    Json(serde_json::json!({
+        "name": name,
+        "map": map,
+        "zone": zone,
+        "position": { "x": x, "y": y, "z": z },
+        "nextAvailableAt": now + unstuck_cooldown_secs(),
+    })).into_response()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharacterService {
+    Rename,
+    Customize,
+    RaceChange,
+    FactionChange,
+}
+
+impl CharacterService {
+    const ALL: [CharacterService; 4] = [Self::Rename, Self::Customize, Self::RaceChange, Self::FactionChange];
+
+    fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.slug() == slug)
+    }
+
+    fn slug(self) -> &'static str {
+        match self {
+            Self::Rename => "rename",
+            Self::Customize => "customize",
+            Self::RaceChange => "race-change",
+            Self::FactionChange => "faction-change",
+        }
+    }
+
+    /// AtLoginFlags bit the worldserver acts on at the next login.
+    fn at_login_flag(self) -> u16 {
+        match self {
+            Self::Rename => 0x01,
+            Self::Customize => 0x08,
+            Self::FactionChange => 0x40,
+            Self::RaceChange => 0x80,
+        }
+    }
+
+    fn env_key(self) -> &'static str {
+        match self {
+            Self::Rename => "RENAME",
+            Self::Customize => "CUSTOMIZE",
+            Self::RaceChange => "RACE_CHANGE",
+            Self::FactionChange => "FACTION_CHANGE",
+        }
+    }
+
+    fn cooldown_secs(self) -> i64 {
+        let default = match self {
+            Self::Rename | Self::Customize => 7 * 24 * 3600,
+            Self::RaceChange | Self::FactionChange => 30 * 24 * 3600,
+        };
+        std::env::var(format!("SERVICE_{}_COOLDOWN_SECS", self.env_key()))
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    }
+
+    /// Points charged for the service; 0 (the default) makes it free.
+    fn cost(self) -> i64 {
+        std::env::var(format!("SERVICE_{}_COST", self.env_key()))
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0)
+    }
+}
+
+const PENDING_SERVICE_FLAGS: u16 = 0x01 | 0x08 | 0x40 | 0x80;
+
+/// Whether `at_login` already carries one of the service flags.
+fn service_pending(at_login: u16) -> bool {
+    at_login & PENDING_SERVICE_FLAGS != 0
+}
+
+pub async fn request_character_service(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path((name, service)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    let service = match CharacterService::from_slug(&service) {
+        Some(s) => s,
+        None => return (StatusCode::BAD_REQUEST, "Unknown service").into_response(),
+    };
+    let accounts = match caller_game_accounts(&state, &claims).await {
+        Ok(a) => a,
+        Err(e) => return e.into_response(),
+    };
+    let character = match load_owned_character(&realm.pool, &claims, &accounts, &name, true).await {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    // The worldserver writes at_login back when it saves an online player,
+    // which would drop the flag we set.
+    if character.online {
+        return (StatusCode::CONFLICT, "Character must be logged out first").into_response();
+    }
+    if service_pending(character.at_login) {
+        return (StatusCode::CONFLICT, "Another service is already pending for this character").into_response();
+    }
+
+    let now = now_secs();
+    let cooldown_key = format!("service:{}:{}:{}", service.slug(), realm.id, character.guid);
+    match claim_cooldown(&state, &cooldown_key, now, service.cooldown_secs()).await {
+        Ok(None) => {},
+        Ok(Some(remaining)) => return (
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("This service is on cooldown for another {} seconds", remaining),
+        ).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+
+    let cost = if claims.role == "admin" { 0 } else { service.cost() };
+    if cost > 0 {
+        match points::charge(&state, &claims.sub, cost).await {
+            Ok(true) => {},
+            Ok(false) => {
+                release_cooldown(&state, &cooldown_key, now).await;
+                return (StatusCode::PAYMENT_REQUIRED, "Not enough points for this service").into_response();
+            },
+            Err(_) => {
+                release_cooldown(&state, &cooldown_key, now).await;
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            },
+        }
+    }
+
+    // The pending check above ran on an earlier read; repeating it in the
+    // UPDATE keeps two different services requested at once from both
+    // being set.
+    let updated = sqlx::query(
+        "UPDATE characters SET at_login = at_login | ? WHERE guid = ? AND online = 0 AND (at_login & ?) = 0",
+    )
+        .bind(service.at_login_flag())
+        .bind(character.guid)
+        .bind(PENDING_SERVICE_FLAGS)
+        .execute(&realm.pool)
+        .await;
+    let failure = match updated {
+        Ok(result) if result.rows_affected() > 0 => None,
+        Ok(_) => Some((StatusCode::CONFLICT, "Character must be logged out and have no other service pending")),
+        Err(e) => {
+            tracing::error!("Failed to set at_login for {}: {}", name, e);
+            Some((StatusCode::INTERNAL_SERVER_ERROR, "Database error"))
+        },
+    };
+    if let Some(failure) = failure {
+        if cost > 0 {
+            points::refund(&state, &claims.sub, cost).await;
+        }
+        release_cooldown(&state, &cooldown_key, now).await;
+        return failure.into_response();
+    }
+
+    let logs: Collection<CharacterServiceLog> = state.mongo.collection("character_service_log");
+    let entry = CharacterServiceLog {
+        id: None,
+        realm: realm.id,
+        guid: character.guid,
+        name: name.clone(),
+        service: service.slug().to_string(),
+        requested_by: claims.sub,
+        cost,
+        at: now,
+    };
+    if let Err(e) = logs.insert_one(entry, None).await {
+        tracing::error!("Failed to record {} for {}: {}", service.slug(), name, e);
+    }
+
+    Json(serde_json::json!({
+        "name": name,
+        "service": service.slug(),
+        "cost": cost,
+        "nextAvailableAt": now + service.cooldown_secs(),
+    })).into_response()
+}
+
+pub async fn list_character_services(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    let accounts = match caller_game_accounts(&state, &claims).await {
+        Ok(a) => a,
+        Err(e) => return e.into_response(),
+    };
+    let character = match load_owned_character(&realm.pool, &claims, &accounts, &name, true).await {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    let logs: Collection<CharacterServiceLog> = state.mongo.collection("character_service_log");
+    let cursor = match logs.find(
+        doc! { "guid": character.guid, "realm": realm.id },
+        FindOptions::builder().sort(doc! { "at": -1 }).build(),
+    ).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    match cursor.try_collect::<Vec<CharacterServiceLog>>().await {
+        Ok(history) => Json(history).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+}
+
+fn max_characters_per_realm() -> i64 {
+    std::env::var("MAX_CHARACTERS_PER_REALM")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeletedCharactersQuery {
+    pub account: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct RestoreCharacterRequest {
+    pub name: Option<String>,
+}
+
+pub async fn list_deleted_characters(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Query(params): Query<DeletedCharactersQuery>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    let accounts = match params.account {
+        Some(account) if claims.role == "admin" => vec![account],
+        Some(_) => return (StatusCode::FORBIDDEN, "Admin access required").into_response(),
+        None => match caller_game_accounts(&state, &claims).await {
+            Ok(a) => a,
+            Err(e) => return e.into_response(),
+        },
+    };
+
+    if accounts.is_empty() {
+        return Json(Vec::<DeletedCharacter>::new()).into_response();
+    }
+
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT guid, deleteInfos_Account, deleteInfos_Name, race, class, gender, level, deleteDate \
+         FROM characters WHERE account = 0 AND deleteInfos_Account IN (",
+    );
+    let mut separated = builder.separated(", ");
+    for account in &accounts {
+        separated.push_bind(*account);
+    }
+    builder.push(") ORDER BY deleteDate DESC");
+
+    let rows = match builder.build().fetch_all(&realm.pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to list deleted characters: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let characters: Vec<DeletedCharacter> = rows.iter().map(|row| {
+        DeletedCharacter {
+            guid: row.try_get::<u32, _>("guid").unwrap_or_default(),
+            account: row.try_get::<Option<u32>, _>("deleteInfos_Account").unwrap_or_default(),
+            name: row.try_get::<Option<String>, _>("deleteInfos_Name").unwrap_or_default(),
+            traits: CharacterTraits::new(
+                row.try_get::<u8, _>("race").unwrap_or_default(),
+                row.try_get::<u8, _>("class").unwrap_or_default(),
+                row.try_get::<u8, _>("gender").ok(),
+                locale,
+            ),
+            level: row.try_get::<u8, _>("level").unwrap_or_default(),
+            deleted_at: row.try_get::<Option<u32>, _>("deleteDate").unwrap_or_default(),
+        }
+    }).collect();
+
+    Json(characters).into_response()
+}
+
+/// Character names as the game stores them: first letter upper case, the
+/// rest lower case.
+fn normalize_name(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + &chars.as_str().to_ascii_lowercase(),
+        None => String::new(),
+    }
+}
+
+pub async fn restore_deleted_character(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(guid): Path<u32>,
+    payload: Option<Json<RestoreCharacterRequest>>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    let accounts = match caller_game_accounts(&state, &claims).await {
+        Ok(a) => a,
+        Err(e) => return e.into_response(),
+    };
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+
+    // The checks and the update share a transaction, with the rows they read
+    // locked, so two restores can't both take the last slot or the same name.
+    let mut tx = match realm.pool.begin().await {
+        Ok(tx) => tx,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    let row = match sqlx::query(
+        "SELECT deleteInfos_Account, deleteInfos_Name FROM characters \
+         WHERE guid = ? AND account = 0 AND deleteInfos_Account IS NOT NULL FOR UPDATE",
+    )
+        .bind(guid)
+        .fetch_optional(&mut *tx)
+        .await {
+            Ok(Some(row)) => row,
+            Ok(None) => return (StatusCode::NOT_FOUND, "Deleted character not found").into_response(),
+            Err(e) => {
+                tracing::error!("Failed to load deleted character {}: {}", guid, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+
+    let account = row.try_get::<Option<u32>, _>("deleteInfos_Account").unwrap_or_default().unwrap_or_default();
+    if claims.role != "admin" && !accounts.contains(&account) {
+        return (StatusCode::FORBIDDEN, "You do not own this character").into_response();
+    }
+
+    let name = match payload.name {
+        Some(n) => {
+            if n.len() < 2 || n.len() > 12 || !n.chars().all(|c| c.is_ascii_alphabetic()) {
+                return (StatusCode::BAD_REQUEST, "Name must be 2-12 letters").into_response();
+            }
+            n
+        },
+        None => row.try_get::<Option<String>, _>("deleteInfos_Name").unwrap_or_default().unwrap_or_default(),
+    };
+    if name.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Original name is unknown, a new name is required").into_response();
+    }
+    let name = normalize_name(&name);
+
+    let taken: i64 = match sqlx::query_scalar("SELECT COUNT(*) FROM characters WHERE name = ? FOR UPDATE")
+        .bind(&name)
+        .fetch_one(&mut *tx)
+        .await {
+            Ok(c) => c,
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+        };
+    if taken > 0 {
+        return (StatusCode::CONFLICT, "Name is already taken, choose a new name").into_response();
+    }
+
+    let count: i64 = match sqlx::query_scalar("SELECT COUNT(*) FROM characters WHERE account = ? FOR UPDATE")
+        .bind(account)
+        .fetch_one(&mut *tx)
+        .await {
+            Ok(c) => c,
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+        };
+    if count >= max_characters_per_realm() {
+        return (StatusCode::CONFLICT, "Account has no free character slots").into_response();
+    }
+
+    if let Err(e) = sqlx::query(
+        "UPDATE characters SET account = ?, name = ?, deleteInfos_Account = NULL, deleteInfos_Name = NULL, deleteDate = NULL \
+         WHERE guid = ?",
+    )
+        .bind(account)
+        .bind(&name)
+        .bind(guid)
+        .execute(&mut *tx)
+        .await {
+            tracing::error!("Failed to restore character {}: {}", guid, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Failed to restore character {}: {}", guid, e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+    sync_character_counts(&state, &realm, &[account]).await;
+
+    tracing::info!("Character {} ({}) restored to account {} by {}", name, guid, account, claims.sub);
+
+    Json(serde_json::json!({
+        "guid": guid,
+        "name": name,
+        "account": account,
+    })).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransferCharacterRequest {
+    #[serde(rename = "targetAccount")]
+    pub target_account: u32,
+}
+
+/// Refreshes `realmcharacters.numchars`, the character counts the client's
+/// realm list shows, for the given accounts.
+async fn sync_character_counts(state: &AppState, realm: &RealmDb, accounts: &[u32]) {
+    for account in accounts {
+        let result = match sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM characters WHERE account = ?")
+            .bind(account)
+            .fetch_one(&realm.pool)
+            .await {
+                Ok(count) => sqlx::query("REPLACE INTO realmcharacters (realmid, acctid, numchars) VALUES (?, ?, ?)")
+                    .bind(realm.id)
+                    .bind(account)
+                    .bind(count)
+                    .execute(&state.mysql_auth)
+                    .await
+                    .map(|_| ()),
+                Err(e) => Err(e),
+            };
+        if let Err(e) = result {
+            tracing::warn!("Failed to update character count of account {} on realm {}: {}", account, realm.id, e);
+        }
+    }
+}
+
+pub async fn transfer_character(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(payload): Json<TransferCharacterRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    let accounts = match caller_game_accounts(&state, &claims).await {
+        Ok(a) => a,
+        Err(e) => return e.into_response(),
+    };
+    let character = match load_owned_character(&realm.pool, &claims, &accounts, &name, false).await {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    let target = payload.target_account;
+    if !accounts.contains(&target) {
+        return (StatusCode::FORBIDDEN, "Target account is not linked to you").into_response();
+    }
+    if target == character.account {
+        return (StatusCode::BAD_REQUEST, "Character already belongs to that account").into_response();
+    }
+    if character.online {
+        return (StatusCode::CONFLICT, "Character must be logged out first").into_response();
+    }
+
+    // The checks and the move share a transaction, with the target account's
+    // characters locked, so parallel transfers can't overfill its slots.
+    let mut tx = match realm.pool.begin().await {
+        Ok(tx) => tx,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    let count: i64 = match sqlx::query_scalar("SELECT COUNT(*) FROM characters WHERE account = ? FOR UPDATE")
+        .bind(target)
+        .fetch_one(&mut *tx)
+        .await {
+            Ok(c) => c,
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+        };
+    if count >= max_characters_per_realm() {
+        return (StatusCode::CONFLICT, "Target account has no free character slots").into_response();
+    }
+
+    // Leadership roles are tied to the character but managed per account in-game,
+    // so they have to be handed over before moving.
+    let guild_master: i64 = match sqlx::query_scalar("SELECT COUNT(*) FROM guild WHERE leaderguid = ? FOR UPDATE")
+        .bind(character.guid)
+        .fetch_one(&mut *tx)
+        .await {
+            Ok(c) => c,
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+        };
+    if guild_master > 0 {
+        return (StatusCode::CONFLICT, "Guild masters must pass leadership before transferring").into_response();
+    }
+
+    let arena_captain: i64 = match sqlx::query_scalar("SELECT COUNT(*) FROM arena_team WHERE captainGuid = ? FOR UPDATE")
+        .bind(character.guid)
+        .fetch_one(&mut *tx)
+        .await {
+            Ok(c) => c,
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+        };
+    if arena_captain > 0 {
+        return (StatusCode::CONFLICT, "Arena team captains must pass captaincy before transferring").into_response();
+    }
+
+    let moved = match sqlx::query("UPDATE characters SET account = ? WHERE guid = ? AND account = ? AND online = 0")
+        .bind(target)
+        .bind(character.guid)
+        .bind(character.account)
+        .execute(&mut *tx)
+        .await {
+            Ok(result) => result.rows_affected(),
+            Err(e) => {
+                tracing::error!("Failed to transfer character {}: {}", name, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        };
+    if moved == 0 {
+        return (StatusCode::CONFLICT, "Character changed during the transfer, try again").into_response();
+    }
+    if let Err(e) = tx.commit().await {
+        tracing::error!("Failed to transfer character {}: {}", name, e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+    sync_character_counts(&state, &realm, &[character.account, target]).await;
+
+    let entry = CharacterTransferLog {
+        id: None,
+        realm: realm.id,
+        guid: character.guid,
+        name: name.clone(),
+        from_account: character.account,
+        to_account: target,
+        requested_by: claims.sub,
+        at: now_secs(),
+    };
+    tracing::info!("Character {} ({}) transferred from account {} to {}", name, character.guid, character.account, target);
+    let logs: Collection<CharacterTransferLog> = state.mongo.collection("character_transfers");
+    if let Err(e) = logs.insert_one(entry, None).await {
+        tracing::error!("Failed to record transfer of {}: {}", name, e);
+    }
+
+    Json(serde_json::json!({
+        "name": name,
+        "fromAccount": character.account,
+        "toAccount": target,
+    })).into_response()
+}
+
+pub async fn list_character_transfers(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let logs: Collection<CharacterTransferLog> = state.mongo.collection("character_transfers");
+    let cursor = match logs.find(doc! {}, FindOptions::builder().sort(doc! { "at": -1 }).limit(200).build()).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    match cursor.try_collect::<Vec<CharacterTransferLog>>().await {
+        Ok(history) => Json(history).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_service_flag_counts_as_pending() {
+        assert_eq!(PENDING_SERVICE_FLAGS, 0xC9);
+        for service in CharacterService::ALL {
+            assert_eq!(service.at_login_flag() & PENDING_SERVICE_FLAGS, service.at_login_flag());
+        }
+    }
+
+    #[test]
+    fn a_pending_service_blocks_every_other_service() {
+        for pending in CharacterService::ALL {
+            // Other at_login bits, like reset spells (0x02), don't interfere.
+            let at_login = pending.at_login_flag() | 0x02;
+            assert!(service_pending(at_login), "{} should block", pending.slug());
+        }
+        assert!(!service_pending(0x02 | 0x04 | 0x20));
+        assert!(!service_pending(0));
+    }
+}
@@ -0,0 +1,17 @@
+//! Time helpers shared by the handlers and the background jobs.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub fn now_secs() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+/// Period of a background job from `var` in seconds, or `default_secs`.
+/// None when it is set to 0, which disables the job.
+pub fn interval_from_env(var: &str, default_secs: u64) -> Option<Duration> {
+    let secs = std::env::var(var)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default_secs);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
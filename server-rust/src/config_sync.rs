@@ -0,0 +1,339 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use mongodb::{bson::doc, Collection};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{AppState, handlers::authenticate, models::ServerConfig};
+
+/// Accepted range for every rate multiplier.
+const MIN_RATE: f64 = 0.1;
+const MAX_RATE: f64 = 100.0;
+
+const MANAGED_HEADER: &str = "# Rates managed by the dashboard";
+
+/// worldserver.conf keys driven by each dashboard rate.
+const XP_KEYS: &[&str] = &["Rate.XP.Kill", "Rate.XP.Quest", "Rate.XP.Quest.DF", "Rate.XP.Explore", "Rate.XP.Pet"];
+const DROP_KEYS: &[&str] = &[
+    "Rate.Drop.Item.Poor",
+    "Rate.Drop.Item.Normal",
+    "Rate.Drop.Item.Uncommon",
+    "Rate.Drop.Item.Rare",
+    "Rate.Drop.Item.Epic",
+    "Rate.Drop.Item.Legendary",
+    "Rate.Drop.Item.Artifact",
+    "Rate.Drop.Item.Referenced",
+];
+const GOLD_KEYS: &[&str] = &["Rate.Drop.Money"];
+const REP_KEYS: &[&str] = &["Rate.Reputation.Gain"];
+
+#[derive(Debug, Deserialize)]
+pub struct SyncRequest {
+    pub reload: Option<bool>,
+}
+
+/// Outcome of writing the rates to worldserver.conf.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub changed: Vec<String>,
+    pub backup: Option<PathBuf>,
+    pub reloaded: bool,
+}
+
+/// Path of worldserver.conf, or None when syncing is not configured.
+fn conf_path() -> Option<PathBuf> {
+    std::env::var("WORLDSERVER_CONF").ok().filter(|p| !p.is_empty()).map(PathBuf::from)
+}
+
+fn reload_by_default() -> bool {
+    std::env::var("CONFIG_SYNC_RELOAD").map(|v| v == "1" || v == "true").unwrap_or(false)
+}
+
+pub fn validate_rates(config: &ServerConfig) -> Result<(), String> {
+    for (label, value) in [
+        ("xpRate", config.xp_rate),
+        ("dropRate", config.drop_rate),
+        ("goldRate", config.gold_rate),
+        ("repRate", config.rep_rate),
+    ] {
+        if !value.is_finite() || !(MIN_RATE..=MAX_RATE).contains(&value) {
+            return Err(format!("{} must be between {} and {}", label, MIN_RATE, MAX_RATE));
+        }
+    }
+    Ok(())
+}
+
+fn rate_values(config: &ServerConfig) -> Vec<(&'static str, f64)> {
+    let mut values = Vec::new();
+    for (keys, rate) in [
+        (XP_KEYS, config.xp_rate),
+        (DROP_KEYS, config.drop_rate),
+        (GOLD_KEYS, config.gold_rate),
+        (REP_KEYS, config.rep_rate),
+    ] {
+        values.extend(keys.iter().map(|k| (*k, rate)));
+    }
+    values
+}
+
+fn format_rate(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Rewrites the given keys in a worldserver.conf body. Only the value of an
+/// uncommented `Key = value` line changes; comments, ordering and unrelated
+/// keys are left alone. Keys missing from the file are appended in a block at
+/// the end. Returns the new body and the keys whose value changed.
+pub fn render(contents: &str, values: &[(&str, f64)]) -> (String, Vec<String>) {
+    let newline = if contents.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut changed = Vec::new();
+    let mut seen = Vec::new();
+
+    let mut lines: Vec<String> = contents.lines().map(|line| {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('#') {
+            return line.to_string();
+        }
+        let Some((key_part, old_value)) = line.split_once('=') else {
+            return line.to_string();
+        };
+        let key = key_part.trim();
+        let Some((_, value)) = values.iter().find(|(k, _)| *k == key) else {
+            return line.to_string();
+        };
+
+        seen.push(key.to_string());
+        let new_value = format_rate(*value);
+        if old_value.trim() == new_value {
+            return line.to_string();
+        }
+        changed.push(key.to_string());
+        format!("{}= {}", key_part, new_value)
+    }).collect();
+
+    let missing: Vec<&(&str, f64)> = values.iter().filter(|(k, _)| !seen.iter().any(|s| s == k)).collect();
+    if !missing.is_empty() {
+        if !lines.iter().any(|l| l == MANAGED_HEADER) {
+            lines.push(String::new());
+            lines.push(MANAGED_HEADER.to_string());
+        }
+        for (key, value) in missing {
+            lines.push(format!("{} = {}", key, format_rate(*value)));
+            changed.push(key.to_string());
+        }
+    }
+
+    let mut rendered = lines.join(newline);
+    if contents.ends_with('\n') || contents.is_empty() {
+        rendered.push_str(newline);
+    }
+    (rendered, changed)
+}
+
+/// Number of `.bak-<timestamp>` copies kept next to worldserver.conf.
+fn backups_to_keep() -> usize {
+    std::env::var("CONFIG_SYNC_BACKUPS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10)
+        .max(1)
+}
+
+fn conf_file_name(path: &Path) -> &str {
+    path.file_name().and_then(|n| n.to_str()).unwrap_or("worldserver.conf")
+}
+
+/// Replaces the file through a temporary copy so the worldserver never reads
+/// a half-written config.
+async fn replace_conf(path: &Path, contents: &str) -> Result<(), String> {
+    let temp = path.with_file_name(format!("{}.tmp", conf_file_name(path)));
+    tokio::fs::write(&temp, contents).await.map_err(|e| format!("write failed: {}", e))?;
+    tokio::fs::rename(&temp, path).await.map_err(|e| format!("write failed: {}", e))
+}
+
+async fn write_conf(path: &Path, original: &str, rendered: &str) -> Result<PathBuf, String> {
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let backup = path.with_file_name(format!("{}.bak-{}", conf_file_name(path), stamp));
+
+    tokio::fs::write(&backup, original).await.map_err(|e| format!("backup failed: {}", e))?;
+    replace_conf(path, rendered).await?;
+    if let Err(e) = prune_backups(path).await {
+        tracing::warn!("Failed to prune worldserver.conf backups: {}", e);
+    }
+    Ok(backup)
+}
+
+/// Deletes all but the newest `CONFIG_SYNC_BACKUPS` backups.
+async fn prune_backups(path: &Path) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    let prefix = format!("{}.bak-", conf_file_name(path));
+
+    let mut backups: Vec<(u64, PathBuf)> = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        let Some(stamp) = name.to_str().and_then(|n| n.strip_prefix(&prefix)).and_then(|s| s.parse().ok()) else {
+            continue;
+        };
+        backups.push((stamp, entry.path()));
+    }
+
+    backups.sort_unstable_by_key(|(stamp, _)| std::cmp::Reverse(*stamp));
+    for (_, old) in backups.into_iter().skip(backups_to_keep()) {
+        tokio::fs::remove_file(old).await?;
+    }
+    Ok(())
+}
+
+/// Renders the config's rates into worldserver.conf and, when `reload` is
+/// set, asks the worldserver to re-read it. If the reload fails the original
+/// file is put back, so an error always leaves the file unchanged. A no-op
+/// when `WORLDSERVER_CONF` is unset.
+pub async fn apply(state: &AppState, config: &ServerConfig, reload: bool) -> Result<SyncReport, String> {
+    let Some(path) = conf_path() else {
+        return Ok(SyncReport::default());
+    };
+    validate_rates(config)?;
+
+    let original = tokio::fs::read_to_string(&path).await.map_err(|e| format!("{}: {}", path.display(), e))?;
+    let (rendered, changed) = render(&original, &rate_values(config));
+
+    let mut report = SyncReport { changed, ..SyncReport::default() };
+    if report.changed.is_empty() {
+        return Ok(report);
+    }
+
+    report.backup = Some(write_conf(&path, &original, &rendered).await?);
+    tracing::info!("Updated {} rate keys in {}", report.changed.len(), path.display());
+
+    if reload {
+        if let Err(e) = state.soap.execute("reload config").await {
+            if let Err(restore) = replace_conf(&path, &original).await {
+                tracing::error!("Failed to restore {} after reload error: {}", path.display(), restore);
+            }
+            return Err(format!("reload failed: {}", e));
+        }
+        report.reloaded = true;
+    }
+    Ok(report)
+}
+
+/// Re-applies the stored rates, e.g. after worldserver.conf was replaced.
+pub async fn sync_server_config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SyncRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+    if conf_path().is_none() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "WORLDSERVER_CONF is not configured").into_response();
+    }
+
+    let collection: Collection<ServerConfig> = state.mongo.collection("server_config");
+    let config = match collection.find_one(doc! {}, None).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return (StatusCode::NOT_FOUND, "No server config saved yet").into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    match apply(&state, &config, payload.reload.unwrap_or_else(reload_by_default)).await {
+        Ok(report) => Json(serde_json::json!({
+            "changed": report.changed,
+            "backup": report.backup.map(|p| p.display().to_string()),
+            "reloaded": report.reloaded,
+        })).into_response(),
+        Err(e) => {
+            tracing::error!("worldserver.conf sync failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Sync failed: {}", e)).into_response()
+        }
+    }
+}
+
+/// Sync run after the admin saves the config through `update_server_config`.
+pub async fn apply_after_update(state: &AppState, config: &ServerConfig) -> Result<(), String> {
+    apply(state, config, reload_by_default()).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONF: &str = "\
+[worldserver]
+
+#    Rate.XP.Kill
+#        Description: Experience rate for kills.
+#        Default:     1
+Rate.XP.Kill = 1
+
+Rate.Drop.Money    = 1
+# Rate.XP.Quest = 7
+PlayerLimit = 100
+";
+
+    #[test]
+    fn rewrites_values_and_keeps_comments_and_unrelated_keys() {
+        let (rendered, changed) = render(CONF, &[("Rate.XP.Kill", 5.0), ("Rate.Drop.Money", 2.5)]);
+
+        assert_eq!(changed, vec!["Rate.XP.Kill", "Rate.Drop.Money"]);
+        assert!(rendered.contains("#        Description: Experience rate for kills.\n"));
+        assert!(rendered.contains("\nRate.XP.Kill = 5\n"));
+        // The spacing before `=` is kept.
+        assert!(rendered.contains("\nRate.Drop.Money    = 2.5\n"));
+        assert!(rendered.contains("\n# Rate.XP.Quest = 7\n"));
+        assert!(rendered.contains("\nPlayerLimit = 100\n"));
+        assert!(!rendered.contains(MANAGED_HEADER));
+    }
+
+    #[test]
+    fn appends_missing_keys_in_a_managed_block() {
+        let (rendered, changed) = render(CONF, &[("Rate.XP.Kill", 1.0), ("Rate.XP.Quest", 3.0)]);
+
+        // A commented-out key doesn't count as present.
+        assert_eq!(changed, vec!["Rate.XP.Quest"]);
+        assert!(rendered.ends_with(&format!("PlayerLimit = 100\n\n{}\nRate.XP.Quest = 3\n", MANAGED_HEADER)));
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let conf = CONF.replace('\n', "\r\n");
+        let (rendered, _) = render(&conf, &[("Rate.XP.Kill", 2.0), ("Rate.XP.Pet", 2.0)]);
+
+        assert!(rendered.contains("\r\nRate.XP.Kill = 2\r\n"));
+        assert!(rendered.ends_with("Rate.XP.Pet = 2\r\n"));
+        assert!(!rendered.replace("\r\n", "").contains('\n'));
+    }
+
+    #[test]
+    fn rendering_twice_changes_nothing() {
+        let values = [("Rate.XP.Kill", 1.75), ("Rate.XP.Quest", 4.0), ("Rate.Reputation.Gain", 0.5)];
+        let (once, _) = render(CONF, &values);
+        let (twice, changed) = render(&once, &values);
+
+        assert_eq!(once, twice);
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn formats_rates_without_trailing_zeros() {
+        assert_eq!(format_rate(1.0), "1");
+        assert_eq!(format_rate(1.5), "1.5");
+        assert_eq!(format_rate(0.25), "0.25");
+        assert_eq!(format_rate(2.999), "3");
+    }
+}
@@ -0,0 +1,63 @@
+//! Minimal reader for the client's WDBC tables, as extracted into the
+//! worldserver's `data/dbc` directory. Only the fixed-width 4-byte field
+//! layout used by the 3.3.5a tables read here is supported.
+
+use std::path::Path;
+
+const HEADER_LEN: usize = 20;
+
+pub struct DbcFile {
+    record_size: usize,
+    records: Vec<u8>,
+    strings: Vec<u8>,
+}
+
+impl DbcFile {
+    /// Opens a table and checks its field count against the expected layout.
+    pub fn open(dir: &Path, name: &str, expected_fields: usize) -> Result<Self, String> {
+        let path = dir.join(name);
+        let raw = std::fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if raw.len() < HEADER_LEN || &raw[0..4] != b"WDBC" {
+            return Err(format!("{}: not a WDBC file", path.display()));
+        }
+
+        let header = |i: usize| u32::from_le_bytes(raw[4 + i * 4..8 + i * 4].try_into().unwrap()) as usize;
+        let (count, fields, record_size, string_size) = (header(0), header(1), header(2), header(3));
+        if fields != expected_fields {
+            return Err(format!("{}: expected {} fields, found {}", path.display(), expected_fields, fields));
+        }
+        if record_size != fields * 4 || raw.len() < HEADER_LEN + count * record_size + string_size {
+            return Err(format!("{}: unexpected layout", path.display()));
+        }
+
+        let strings_at = HEADER_LEN + count * record_size;
+        Ok(DbcFile {
+            record_size,
+            records: raw[HEADER_LEN..strings_at].to_vec(),
+            strings: raw[strings_at..strings_at + string_size].to_vec(),
+        })
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = DbcRecord<'_>> {
+        self.records.chunks_exact(self.record_size).map(move |data| DbcRecord { data, strings: &self.strings })
+    }
+}
+
+pub struct DbcRecord<'a> {
+    data: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl DbcRecord<'_> {
+    pub fn u32(&self, field: usize) -> u32 {
+        u32::from_le_bytes(self.data[field * 4..field * 4 + 4].try_into().unwrap())
+    }
+
+    /// String field, or None when empty or out of the string block.
+    pub fn string(&self, field: usize) -> Option<String> {
+        let start = self.u32(field) as usize;
+        let tail = self.strings.get(start..)?;
+        let end = tail.iter().position(|b| *b == 0)?;
+        (end > 0).then(|| String::from_utf8_lossy(&tail[..end]).into_owned())
+    }
+}
@@ -0,0 +1,554 @@
+//! Game-domain enums for the ids stored in the characters DB, with the
+//! display names the dashboard shows in each of its languages.
+
+use axum::{http::HeaderMap, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+
+/// Dashboard languages, matching `Language` in the frontend's I18nContext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    PtPt,
+    PtBr,
+    En,
+}
+
+impl Locale {
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.trim().to_lowercase();
+        if tag == "pt-br" {
+            Some(Self::PtBr)
+        } else if tag.starts_with("pt") {
+            Some(Self::PtPt)
+        } else if tag.starts_with("en") {
+            Some(Self::En)
+        } else {
+            None
+        }
+    }
+
+    /// Picks the first supported language from `Accept-Language`, falling back
+    /// to pt-PT like the frontend does.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get("Accept-Language")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').find_map(|part| Self::from_tag(part.split(';').next().unwrap_or(""))))
+            .unwrap_or(Self::PtPt)
+    }
+
+    fn pick(self, pt_pt: &'static str, pt_br: &'static str, en: &'static str) -> &'static str {
+        match self {
+            Self::PtPt => pt_pt,
+            Self::PtBr => pt_br,
+            Self::En => en,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Faction {
+    Alliance,
+    Horde,
+}
+
+impl Faction {
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        match slug.to_lowercase().as_str() {
+            "alliance" => Some(Self::Alliance),
+            "horde" => Some(Self::Horde),
+            _ => None,
+        }
+    }
+
+    pub fn slug(self) -> &'static str {
+        match self {
+            Self::Alliance => "alliance",
+            Self::Horde => "horde",
+        }
+    }
+
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            Self::Alliance => locale.pick("Aliança", "Aliança", "Alliance"),
+            Self::Horde => locale.pick("Horda", "Horda", "Horde"),
+        }
+    }
+
+    pub fn races(self) -> impl Iterator<Item = Race> {
+        Race::ALL.into_iter().filter(move |r| r.faction() == self)
+    }
+
+    /// Race ids of this faction as a comma-separated list for SQL `IN (...)`.
+    /// Built from constants only, so it is safe to inline into a query.
+    pub fn race_ids_sql(self) -> String {
+        self.races().map(|r| (r as u8).to_string()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Race {
+    Human = 1,
+    Orc = 2,
+    Dwarf = 3,
+    NightElf = 4,
+    Undead = 5,
+    Tauren = 6,
+    Gnome = 7,
+    Troll = 8,
+    BloodElf = 10,
+    Draenei = 11,
+}
+
+impl Race {
+    pub const ALL: [Race; 10] = [
+        Self::Human, Self::Orc, Self::Dwarf, Self::NightElf, Self::Undead,
+        Self::Tauren, Self::Gnome, Self::Troll, Self::BloodElf, Self::Draenei,
+    ];
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| *r as u8 == id)
+    }
+
+    pub fn slug(self) -> &'static str {
+        match self {
+            Self::Human => "human",
+            Self::Orc => "orc",
+            Self::Dwarf => "dwarf",
+            Self::NightElf => "night-elf",
+            Self::Undead => "undead",
+            Self::Tauren => "tauren",
+            Self::Gnome => "gnome",
+            Self::Troll => "troll",
+            Self::BloodElf => "blood-elf",
+            Self::Draenei => "draenei",
+        }
+    }
+
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            Self::Human => locale.pick("Humano", "Humano", "Human"),
+            Self::Orc => locale.pick("Orc", "Orc", "Orc"),
+            Self::Dwarf => locale.pick("Anão", "Anão", "Dwarf"),
+            Self::NightElf => locale.pick("Elfo da Noite", "Elfo Noturno", "Night Elf"),
+            Self::Undead => locale.pick("Morto-vivo", "Morto-vivo", "Undead"),
+            Self::Tauren => locale.pick("Tauren", "Tauren", "Tauren"),
+            Self::Gnome => locale.pick("Gnomo", "Gnomo", "Gnome"),
+            Self::Troll => locale.pick("Troll", "Troll", "Troll"),
+            Self::BloodElf => locale.pick("Elfo Sangrento", "Elfo Sangrento", "Blood Elf"),
+            Self::Draenei => locale.pick("Draenei", "Draenei", "Draenei"),
+        }
+    }
+
+    pub fn faction(self) -> Faction {
+        match self {
+            Self::Human | Self::Dwarf | Self::NightElf | Self::Gnome | Self::Draenei => Faction::Alliance,
+            Self::Orc | Self::Undead | Self::Tauren | Self::Troll | Self::BloodElf => Faction::Horde,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Warrior = 1,
+    Paladin = 2,
+    Hunter = 3,
+    Rogue = 4,
+    Priest = 5,
+    DeathKnight = 6,
+    Shaman = 7,
+    Mage = 8,
+    Warlock = 9,
+    Druid = 11,
+}
+
+impl Class {
+    pub const ALL: [Class; 10] = [
+        Self::Warrior, Self::Paladin, Self::Hunter, Self::Rogue, Self::Priest,
+        Self::DeathKnight, Self::Shaman, Self::Mage, Self::Warlock, Self::Druid,
+    ];
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| *c as u8 == id)
+    }
+
+    pub fn slug(self) -> &'static str {
+        match self {
+            Self::Warrior => "warrior",
+            Self::Paladin => "paladin",
+            Self::Hunter => "hunter",
+            Self::Rogue => "rogue",
+            Self::Priest => "priest",
+            Self::DeathKnight => "death-knight",
+            Self::Shaman => "shaman",
+            Self::Mage => "mage",
+            Self::Warlock => "warlock",
+            Self::Druid => "druid",
+        }
+    }
+
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            Self::Warrior => locale.pick("Guerreiro", "Guerreiro", "Warrior"),
+            Self::Paladin => locale.pick("Paladino", "Paladino", "Paladin"),
+            Self::Hunter => locale.pick("Caçador", "Caçador", "Hunter"),
+            Self::Rogue => locale.pick("Ladino", "Ladino", "Rogue"),
+            Self::Priest => locale.pick("Sacerdote", "Sacerdote", "Priest"),
+            Self::DeathKnight => locale.pick("Cavaleiro da Morte", "Cavaleiro da Morte", "Death Knight"),
+            Self::Shaman => locale.pick("Xamã", "Xamã", "Shaman"),
+            Self::Mage => locale.pick("Mago", "Mago", "Mage"),
+            Self::Warlock => locale.pick("Bruxo", "Bruxo", "Warlock"),
+            Self::Druid => locale.pick("Druida", "Druida", "Druid"),
+        }
+    }
+
+    /// Primary power bar. Druids shift between forms but start on mana.
+    pub fn power_type(self) -> PowerType {
+        match self {
+            Self::Warrior => PowerType::Rage,
+            Self::Rogue => PowerType::Energy,
+            Self::DeathKnight => PowerType::RunicPower,
+            _ => PowerType::Mana,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gender {
+    Male = 0,
+    Female = 1,
+}
+
+impl Gender {
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::Male),
+            1 => Some(Self::Female),
+            _ => None,
+        }
+    }
+
+    pub fn slug(self) -> &'static str {
+        match self {
+            Self::Male => "male",
+            Self::Female => "female",
+        }
+    }
+
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            Self::Male => locale.pick("Masculino", "Masculino", "Male"),
+            Self::Female => locale.pick("Feminino", "Feminino", "Female"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerType {
+    Mana = 0,
+    Rage = 1,
+    Energy = 3,
+    RunicPower = 6,
+}
+
+impl PowerType {
+    pub fn slug(self) -> &'static str {
+        match self {
+            Self::Mana => "mana",
+            Self::Rage => "rage",
+            Self::Energy => "energy",
+            Self::RunicPower => "runic-power",
+        }
+    }
+
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            Self::Mana => locale.pick("Mana", "Mana", "Mana"),
+            Self::Rage => locale.pick("Raiva", "Raiva", "Rage"),
+            Self::Energy => locale.pick("Energia", "Energia", "Energy"),
+            Self::RunicPower => locale.pick("Poder Rúnico", "Poder Rúnico", "Runic Power"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReputationRank {
+    Hated,
+    Hostile,
+    Unfriendly,
+    Neutral,
+    Friendly,
+    Honored,
+    Revered,
+    Exalted,
+}
+
+impl ReputationRank {
+    /// Lower bound of every rank, in raw standing points.
+    const THRESHOLDS: [(ReputationRank, i32); 8] = [
+        (Self::Hated, -42000),
+        (Self::Hostile, -6000),
+        (Self::Unfriendly, -3000),
+        (Self::Neutral, 0),
+        (Self::Friendly, 3000),
+        (Self::Honored, 9000),
+        (Self::Revered, 21000),
+        (Self::Exalted, 42000),
+    ];
+
+    /// Rank for a standing, with the progress into it and the size of the rank.
+    pub fn from_standing(standing: i32) -> (Self, i32, i32) {
+        let standing = standing.clamp(-42000, 42999);
+        let index = Self::THRESHOLDS.iter().rposition(|(_, min)| standing >= *min).unwrap_or(0);
+        let (rank, min) = Self::THRESHOLDS[index];
+        let next = Self::THRESHOLDS.get(index + 1).map(|(_, m)| *m).unwrap_or(43000);
+        (rank, standing - min, next - min)
+    }
+
+    pub fn slug(self) -> &'static str {
+        match self {
+            Self::Hated => "hated",
+            Self::Hostile => "hostile",
+            Self::Unfriendly => "unfriendly",
+            Self::Neutral => "neutral",
+            Self::Friendly => "friendly",
+            Self::Honored => "honored",
+            Self::Revered => "revered",
+            Self::Exalted => "exalted",
+        }
+    }
+
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            Self::Hated => locale.pick("Odiado", "Odiado", "Hated"),
+            Self::Hostile => locale.pick("Hostil", "Hostil", "Hostile"),
+            Self::Unfriendly => locale.pick("Inamistoso", "Inamistoso", "Unfriendly"),
+            Self::Neutral => locale.pick("Neutro", "Neutro", "Neutral"),
+            Self::Friendly => locale.pick("Amigável", "Respeitado", "Friendly"),
+            Self::Honored => locale.pick("Honrado", "Honrado", "Honored"),
+            Self::Revered => locale.pick("Reverenciado", "Reverenciado", "Revered"),
+            Self::Exalted => locale.pick("Exaltado", "Exaltado", "Exalted"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemQuality {
+    Poor = 0,
+    Common = 1,
+    Uncommon = 2,
+    Rare = 3,
+    Epic = 4,
+    Legendary = 5,
+    Artifact = 6,
+    Heirloom = 7,
+}
+
+impl ItemQuality {
+    pub const ALL: [ItemQuality; 8] = [
+        Self::Poor, Self::Common, Self::Uncommon, Self::Rare,
+        Self::Epic, Self::Legendary, Self::Artifact, Self::Heirloom,
+    ];
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|q| *q as u8 == id)
+    }
+
+    /// Accepts either the numeric id or the slug.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.parse::<u8>() {
+            Ok(id) => Self::from_id(id),
+            Err(_) => Self::ALL.into_iter().find(|q| q.slug() == value.to_lowercase()),
+        }
+    }
+
+    pub fn slug(self) -> &'static str {
+        match self {
+            Self::Poor => "poor",
+            Self::Common => "common",
+            Self::Uncommon => "uncommon",
+            Self::Rare => "rare",
+            Self::Epic => "epic",
+            Self::Legendary => "legendary",
+            Self::Artifact => "artifact",
+            Self::Heirloom => "heirloom",
+        }
+    }
+
+    pub fn name(self, locale: Locale) -> &'static str {
+        match self {
+            Self::Poor => locale.pick("Fraco", "Inferior", "Poor"),
+            Self::Common => locale.pick("Comum", "Comum", "Common"),
+            Self::Uncommon => locale.pick("Incomum", "Incomum", "Uncommon"),
+            Self::Rare => locale.pick("Raro", "Raro", "Rare"),
+            Self::Epic => locale.pick("Épico", "Épico", "Epic"),
+            Self::Legendary => locale.pick("Lendário", "Lendário", "Legendary"),
+            Self::Artifact => locale.pick("Artefacto", "Artefato", "Artifact"),
+            Self::Heirloom => locale.pick("Herança", "Herança", "Heirloom"),
+        }
+    }
+}
+
+/// A copper amount split the way the game displays it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Money {
+    pub total: u64,
+    pub gold: u64,
+    pub silver: u8,
+    pub copper: u8,
+}
+
+impl Money {
+    pub fn from_copper(total: u64) -> Self {
+        Money {
+            total,
+            gold: total / 10_000,
+            silver: ((total / 100) % 100) as u8,
+            copper: (total % 100) as u8,
+        }
+    }
+}
+
+/// Race/class/gender ids together with their slugs and localized names, for
+/// flattening into any response that describes a character.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CharacterTraits {
+    pub race: u8,
+    #[serde(rename = "raceSlug")]
+    pub race_slug: Option<String>,
+    #[serde(rename = "raceName")]
+    pub race_name: Option<String>,
+    pub class: u8,
+    #[serde(rename = "classSlug")]
+    pub class_slug: Option<String>,
+    #[serde(rename = "className")]
+    pub class_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gender: Option<u8>,
+    #[serde(rename = "genderSlug", skip_serializing_if = "Option::is_none")]
+    pub gender_slug: Option<String>,
+    #[serde(rename = "genderName", skip_serializing_if = "Option::is_none")]
+    pub gender_name: Option<String>,
+    pub faction: Option<String>,
+    #[serde(rename = "factionName")]
+    pub faction_name: Option<String>,
+    #[serde(rename = "powerType")]
+    pub power_type: Option<String>,
+    #[serde(rename = "powerTypeName")]
+    pub power_type_name: Option<String>,
+}
+
+impl CharacterTraits {
+    pub fn new(race: u8, class: u8, gender: Option<u8>, locale: Locale) -> Self {
+        let r = Race::from_id(race);
+        let c = Class::from_id(class);
+        let g = gender.and_then(Gender::from_id);
+        let f = r.map(Race::faction);
+        let p = c.map(Class::power_type);
+
+        CharacterTraits {
+            race,
+            race_slug: r.map(|r| r.slug().to_string()),
+            race_name: r.map(|r| r.name(locale).to_string()),
+            class,
+            class_slug: c.map(|c| c.slug().to_string()),
+            class_name: c.map(|c| c.name(locale).to_string()),
+            gender,
+            gender_slug: g.map(|g| g.slug().to_string()),
+            gender_name: g.map(|g| g.name(locale).to_string()),
+            faction: f.map(|f| f.slug().to_string()),
+            faction_name: f.map(|f| f.name(locale).to_string()),
+            power_type: p.map(|p| p.slug().to_string()),
+            power_type_name: p.map(|p| p.name(locale).to_string()),
+        }
+    }
+}
+
+/// Lookup tables for clients that only have ids.
+pub async fn get_reference(headers: HeaderMap) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+
+    let races: Vec<serde_json::Value> = Race::ALL.iter().map(|r| {
+        serde_json::json!({
+            "id": *r as u8,
+            "slug": r.slug(),
+            "name": r.name(locale),
+            "faction": r.faction().slug(),
+        })
+    }).collect();
+
+    let classes: Vec<serde_json::Value> = Class::ALL.iter().map(|c| {
+        serde_json::json!({
+            "id": *c as u8,
+            "slug": c.slug(),
+            "name": c.name(locale),
+            "powerType": c.power_type().slug(),
+        })
+    }).collect();
+
+    let factions: Vec<serde_json::Value> = [Faction::Alliance, Faction::Horde].iter().map(|f| {
+        serde_json::json!({ "slug": f.slug(), "name": f.name(locale) })
+    }).collect();
+
+    Json(serde_json::json!({
+        "races": races,
+        "classes": classes,
+        "factions": factions,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standing_progress_is_relative_to_the_rank() {
+        assert_eq!(ReputationRank::from_standing(0), (ReputationRank::Neutral, 0, 3000));
+        assert_eq!(ReputationRank::from_standing(2999), (ReputationRank::Neutral, 2999, 3000));
+        assert_eq!(ReputationRank::from_standing(3000), (ReputationRank::Friendly, 0, 6000));
+        assert_eq!(ReputationRank::from_standing(-1), (ReputationRank::Unfriendly, 2999, 3000));
+        assert_eq!(ReputationRank::from_standing(25000), (ReputationRank::Revered, 4000, 21000));
+    }
+
+    #[test]
+    fn standing_is_clamped_to_the_game_limits() {
+        assert_eq!(ReputationRank::from_standing(-50000), (ReputationRank::Hated, 0, 36000));
+        assert_eq!(ReputationRank::from_standing(42999), (ReputationRank::Exalted, 999, 1000));
+        assert_eq!(ReputationRank::from_standing(99999), (ReputationRank::Exalted, 999, 1000));
+    }
+
+    #[test]
+    fn copper_splits_into_gold_silver_and_copper() {
+        let money = Money::from_copper(1_234_567);
+        assert_eq!((money.total, money.gold, money.silver, money.copper), (1_234_567, 123, 45, 67));
+
+        let money = Money::from_copper(99);
+        assert_eq!((money.gold, money.silver, money.copper), (0, 0, 99));
+    }
+
+    #[test]
+    fn gold_is_not_capped() {
+        let money = Money::from_copper(u64::MAX);
+        assert_eq!(money.gold, u64::MAX / 10_000);
+        assert_eq!((money.silver, money.copper), (16, 15));
+    }
+
+    fn accept_language(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("Accept-Language", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn locale_is_the_first_supported_language() {
+        assert_eq!(Locale::from_headers(&accept_language("pt-BR,pt;q=0.9,en;q=0.8")), Locale::PtBr);
+        assert_eq!(Locale::from_headers(&accept_language("fr-FR, en-US;q=0.7")), Locale::En);
+        assert_eq!(Locale::from_headers(&accept_language("pt-PT")), Locale::PtPt);
+        assert_eq!(Locale::from_headers(&accept_language("PT-br")), Locale::PtBr);
+    }
+
+    #[test]
+    fn locale_falls_back_to_pt_pt() {
+        assert_eq!(Locale::from_headers(&HeaderMap::new()), Locale::PtPt);
+        assert_eq!(Locale::from_headers(&accept_language("de-DE,fr;q=0.5")), Locale::PtPt);
+    }
+}
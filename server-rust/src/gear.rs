@@ -0,0 +1,322 @@
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, to_document},
+    options::{FindOptions, IndexOptions, UpdateOptions},
+    Collection, IndexModel,
+};
+use sqlx::{MySql, QueryBuilder, Row};
+use std::collections::HashMap;
+
+use crate::{
+    AppState,
+    clock::{interval_from_env, now_secs},
+    items::{templates_by_entry, ItemTemplate},
+    models::CharacterGear,
+    realms::RealmDb,
+};
+
+/// Equipment slots counted for average item level: head to ranged, without
+/// the shirt (3) and tabard (18).
+const ITEM_LEVEL_SLOTS: [u8; 17] = [0, 1, 2, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17];
+const SLOT_MAIN_HAND: u8 = 15;
+const SLOT_OFF_HAND: u8 = 16;
+const INVTYPE_2HWEAPON: u8 = 17;
+
+fn cache_ttl_secs() -> i64 {
+    std::env::var("GEAR_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(900)
+}
+
+/// GearScore slot weight by item_template.InventoryType.
+fn slot_modifier(inventory_type: u8) -> f64 {
+    match inventory_type {
+        1 | 5 | 7 | 13 | 14 | 20 | 21 | 22 | 23 => 1.0,
+        17 => 2.0,
+        3 | 6 | 8 | 10 => 0.75,
+        2 | 9 | 11 | 12 | 16 => 0.5625,
+        15 | 25 | 26 | 28 => 0.3164,
+        _ => 0.0,
+    }
+}
+
+/// GearScore of a single item, following the GearScoreLite addon formula.
+fn item_score(template: &ItemTemplate, modifier: f64) -> f64 {
+    let mut item_level = template.item_level as f64;
+    let (rarity, quality_scale) = match template.quality {
+        0 | 1 => (2, 0.005),
+        5 => (4, 1.3),
+        7 => {
+            item_level = 187.05;
+            (3, 1.0)
+        },
+        q => (q.clamp(2, 4), 1.0),
+    };
+
+    let (a, b) = if item_level > 120.0 {
+        match rarity {
+            4 => (91.45, 0.65),
+            3 => (81.375, 0.8125),
+            _ => (73.0, 1.0),
+        }
+    } else {
+        match rarity {
+            4 => (26.0, 1.2),
+            3 => (0.75, 1.8),
+            _ => (8.0, 2.0),
+        }
+    };
+
+    (((item_level - a) / b) * modifier * 1.8618 * quality_scale).floor().max(0.0)
+}
+
+/// Average item level and gear score for one character's equipped items,
+/// given as (slot, item entry).
+fn score_equipment(realm: u32, guid: u32, equipped: &[(u8, u32)], templates: &HashMap<u32, ItemTemplate>) -> CharacterGear {
+    let by_slot: HashMap<u8, &ItemTemplate> = equipped.iter()
+        .filter_map(|(slot, entry)| templates.get(entry).map(|t| (*slot, t)))
+        .collect();
+
+    let main_hand = by_slot.get(&SLOT_MAIN_HAND);
+    let off_hand = by_slot.get(&SLOT_OFF_HAND);
+    let two_hander = main_hand.is_some_and(|t| t.inventory_type == INVTYPE_2HWEAPON);
+    // Titan's Grip: two two-handers are weighted as a pair of one-handers.
+    let titans_grip = two_hander && off_hand.is_some_and(|t| t.inventory_type == INVTYPE_2HWEAPON);
+
+    let mut level_sum = 0u32;
+    for slot in ITEM_LEVEL_SLOTS {
+        if let Some(t) = by_slot.get(&slot) {
+            level_sum += t.item_level as u32;
+        }
+    }
+    // A two-hander fills the empty off-hand slot too.
+    if two_hander && off_hand.is_none() {
+        level_sum += main_hand.map(|t| t.item_level as u32).unwrap_or(0);
+    }
+
+    let gear_score: f64 = by_slot.iter().map(|(slot, t)| {
+        let mut modifier = slot_modifier(t.inventory_type);
+        if titans_grip && (*slot == SLOT_MAIN_HAND || *slot == SLOT_OFF_HAND) {
+            modifier /= 2.0;
+        }
+        item_score(t, modifier)
+    }).sum();
+
+    CharacterGear {
+        realm,
+        guid,
+        average_item_level: (level_sum as f64 / ITEM_LEVEL_SLOTS.len() as f64 * 10.0).round() / 10.0,
+        gear_score: gear_score as u32,
+        equipped_items: by_slot.len() as u32,
+        updated_at: now_secs(),
+    }
+}
+
+/// Equipped items grouped by character guid. `guid = None` loads every
+/// player character, including the ones with nothing equipped.
+async fn load_equipment(pool: &sqlx::MySqlPool, guid: Option<u32>) -> Result<HashMap<u32, Vec<(u8, u32)>>, sqlx::Error> {
+    let mut equipment: HashMap<u32, Vec<(u8, u32)>> = HashMap::new();
+    if guid.is_none() {
+        let guids: Vec<u32> = sqlx::query_scalar("SELECT guid FROM characters WHERE account <> 0")
+            .fetch_all(pool)
+            .await?;
+        equipment.extend(guids.into_iter().map(|guid| (guid, Vec::new())));
+    }
+
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT ci.guid, ci.slot, ii.itemEntry \
+         FROM character_inventory ci \
+         JOIN item_instance ii ON ii.guid = ci.item \
+         JOIN characters c ON c.guid = ci.guid \
+         WHERE ci.bag = 0 AND ci.slot < 19 AND c.account <> 0",
+    );
+    if let Some(guid) = guid {
+        builder.push(" AND ci.guid = ").push_bind(guid);
+    }
+
+    for row in builder.build().fetch_all(pool).await? {
+        equipment.entry(row.try_get::<u32, _>("guid").unwrap_or_default()).or_default().push((
+            row.try_get::<u8, _>("slot").unwrap_or_default(),
+            row.try_get::<u32, _>("itemEntry").unwrap_or_default(),
+        ));
+    }
+    Ok(equipment)
+}
+
+async fn store(state: &AppState, gear: &CharacterGear) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let collection: Collection<CharacterGear> = state.mongo.collection("character_gear");
+    collection.update_one(
+        doc! { "realm": gear.realm, "guid": gear.guid },
+        doc! { "$set": to_document(gear)? },
+        UpdateOptions::builder().upsert(true).build(),
+    ).await?;
+    Ok(())
+}
+
+async fn compute(state: &AppState, realm: &RealmDb, guid: u32) -> Result<CharacterGear, Box<dyn std::error::Error + Send + Sync>> {
+    let equipped = load_equipment(&realm.pool, Some(guid)).await?.remove(&guid).unwrap_or_default();
+    let entries: Vec<u32> = equipped.iter().map(|(_, e)| *e).collect();
+    let templates = templates_by_entry(&state.mysql_world, &entries).await?;
+
+    let gear = score_equipment(realm.id, guid, &equipped, &templates);
+    store(state, &gear).await?;
+    Ok(gear)
+}
+
+/// Cached gear for a character, recomputed when older than
+/// `GEAR_CACHE_TTL_SECS`. Failures are logged and reported as None so the
+/// armory still renders without gear data.
+pub async fn character_gear(state: &AppState, realm: &RealmDb, guid: u32) -> Option<CharacterGear> {
+    let collection: Collection<CharacterGear> = state.mongo.collection("character_gear");
+    if let Ok(Some(cached)) = collection.find_one(doc! { "realm": realm.id, "guid": guid }, None).await {
+        if now_secs() - cached.updated_at < cache_ttl_secs() {
+            return Some(cached);
+        }
+    }
+
+    match compute(state, realm, guid).await {
+        Ok(gear) => Some(gear),
+        Err(e) => {
+            tracing::warn!("Failed to compute gear for {}: {}", guid, e);
+            None
+        }
+    }
+}
+
+/// Cached gear for several characters, without recomputing.
+pub async fn cached_gear(state: &AppState, realm: &RealmDb, guids: &[u32]) -> HashMap<u32, CharacterGear> {
+    let collection: Collection<CharacterGear> = state.mongo.collection("character_gear");
+    let mut gear = HashMap::new();
+    if guids.is_empty() {
+        return gear;
+    }
+
+    let cursor = match collection.find(doc! { "realm": realm.id, "guid": { "$in": guids.to_vec() } }, None).await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("Failed to load cached gear: {}", e);
+            return gear;
+        }
+    };
+    match cursor.try_collect::<Vec<CharacterGear>>().await {
+        Ok(entries) => {
+            for g in entries {
+                gear.insert(g.guid, g);
+            }
+        },
+        Err(e) => tracing::warn!("Failed to load cached gear: {}", e),
+    }
+    gear
+}
+
+/// Characters with the best cached value for `field` (gearScore or
+/// averageItemLevel), highest first.
+pub async fn top_by(state: &AppState, realm: &RealmDb, field: &str, limit: i64) -> Result<Vec<CharacterGear>, mongodb::error::Error> {
+    let collection: Collection<CharacterGear> = state.mongo.collection("character_gear");
+    let options = FindOptions::builder().sort(doc! { field: -1 }).limit(limit).build();
+    collection.find(doc! { "realm": realm.id }, options).await?.try_collect().await
+}
+
+/// Starts the background task that recomputes gear for every character so
+/// gear rankings cover players nobody has looked up.
+/// `GEAR_SCORE_INTERVAL_SECS=0` disables it.
+pub fn spawn_refresher(state: AppState) {
+    let period = interval_from_env("GEAR_SCORE_INTERVAL_SECS", 900);
+
+    tokio::spawn(async move {
+        // The cache is also filled on demand, so the index is set up even
+        // when the refresher is disabled.
+        let collection: Collection<CharacterGear> = state.mongo.collection("character_gear");
+        let index = IndexModel::builder()
+            .keys(doc! { "realm": 1, "guid": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        if let Err(e) = collection.create_index(index, None).await {
+            tracing::warn!("Failed to create character_gear index: {}", e);
+        }
+
+        let Some(period) = period else {
+            tracing::info!("Gear score refresher disabled");
+            return;
+        };
+
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            for (realm, pool) in state.realms.all() {
+                match refresh_all(&state, realm, &pool).await {
+                    Ok(count) => tracing::info!("Refreshed gear scores for {} characters on realm {}", count, realm),
+                    Err(e) => tracing::warn!("Gear score refresh failed for realm {}: {}", realm, e),
+                }
+            }
+        }
+    });
+}
+
+/// Rescores every character of the realm, then drops the cache entries the
+/// run didn't touch, which belong to deleted characters.
+async fn refresh_all(state: &AppState, realm: u32, pool: &sqlx::MySqlPool) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let started = now_secs();
+    let equipment = load_equipment(pool, None).await?;
+    let mut entries: Vec<u32> = equipment.values().flatten().map(|(_, e)| *e).collect();
+    entries.sort_unstable();
+    entries.dedup();
+    let templates = templates_by_entry(&state.mysql_world, &entries).await?;
+
+    for (guid, equipped) in &equipment {
+        store(state, &score_equipment(realm, *guid, equipped, &templates)).await?;
+    }
+
+    let collection: Collection<CharacterGear> = state.mongo.collection("character_gear");
+    let purged = collection.delete_many(doc! { "realm": realm, "updatedAt": { "$lt": started } }, None).await?;
+    if purged.deleted_count > 0 {
+        tracing::info!("Removed cached gear of {} deleted characters on realm {}", purged.deleted_count, realm);
+    }
+    Ok(equipment.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(quality: u8, item_level: u16, inventory_type: u8) -> ItemTemplate {
+        ItemTemplate { name: String::new(), quality, item_level, required_level: 0, inventory_type }
+    }
+
+    fn templates() -> HashMap<u32, ItemTemplate> {
+        HashMap::from([
+            (1, template(1, 1, 4)),
+            (2, template(4, 245, 5)),
+            (3, template(4, 200, INVTYPE_2HWEAPON)),
+        ])
+    }
+
+    #[test]
+    fn two_hander_counts_for_the_empty_off_hand() {
+        // Shirt, chest, two-hander and an entry missing from item_template.
+        let gear = score_equipment(1, 7, &[(3, 1), (4, 2), (SLOT_MAIN_HAND, 3), (0, 999)], &templates());
+
+        assert_eq!((gear.realm, gear.guid), (1, 7));
+        assert_eq!(gear.equipped_items, 3);
+        // (245 + 200 + 200) / 17 slots; the shirt isn't counted.
+        assert_eq!(gear.average_item_level, 37.9);
+        assert_eq!(gear.gear_score, 439 + 621);
+    }
+
+    #[test]
+    fn titans_grip_weighs_two_handers_as_one_handers() {
+        let gear = score_equipment(1, 7, &[(SLOT_MAIN_HAND, 3), (SLOT_OFF_HAND, 3)], &templates());
+
+        assert_eq!(gear.average_item_level, 23.5);
+        assert_eq!(gear.gear_score, 310 * 2);
+    }
+
+    #[test]
+    fn nothing_equipped_scores_zero() {
+        let gear = score_equipment(1, 7, &[], &templates());
+
+        assert_eq!((gear.equipped_items, gear.gear_score), (0, 0));
+        assert_eq!(gear.average_item_level, 0.0);
+    }
+}
@@ -0,0 +1,561 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    options::FindOptions,
+    Collection,
+};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    clock::now_secs,
+    handlers::{authenticate, current_role, Claims, ROLES},
+    models::{GmAuditEntry, GmConfirmation, User},
+    soap::output_lines,
+};
+
+const MAX_MAIL_ITEMS: usize = 12;
+const MAX_MUTE_MINUTES: u32 = 7 * 24 * 60;
+/// How long a confirmation token stays valid.
+const CONFIRMATION_TTL_SECS: i64 = 120;
+
+/// Largest amount of copper a single mail may carry; 1000 gold by default.
+fn max_mail_copper() -> u64 {
+    std::env::var("GM_MAX_MAIL_COPPER")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10_000_000)
+}
+
+/// Approved GM actions. Anything else needs raw console access.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GmActionKind {
+    Kick,
+    Teleport,
+    SendItems,
+    SendMoney,
+    Revive,
+    Mute,
+    Unmute,
+}
+
+impl GmActionKind {
+    const ALL: [GmActionKind; 7] = [
+        Self::Kick, Self::Teleport, Self::SendItems, Self::SendMoney,
+        Self::Revive, Self::Mute, Self::Unmute,
+    ];
+
+    fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.slug() == slug)
+    }
+
+    fn slug(self) -> &'static str {
+        match self {
+            Self::Kick => "kick",
+            Self::Teleport => "teleport",
+            Self::SendItems => "send-items",
+            Self::SendMoney => "send-money",
+            Self::Revive => "revive",
+            Self::Mute => "mute",
+            Self::Unmute => "unmute",
+        }
+    }
+
+    /// Parameters as (name, type) for the catalogue; `?` marks optional ones.
+    fn params(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::Kick => &[("player", "string"), ("reason", "string?")],
+            Self::Teleport => &[("player", "string"), ("location", "string")],
+            Self::SendItems => &[("player", "string"), ("subject", "string"), ("body", "string?"), ("items", "[{entry, count}]")],
+            Self::SendMoney => &[("player", "string"), ("subject", "string"), ("body", "string?"), ("copper", "number")],
+            Self::Revive => &[("player", "string")],
+            Self::Mute => &[("player", "string"), ("minutes", "number"), ("reason", "string?")],
+            Self::Unmute => &[("player", "string")],
+        }
+    }
+
+    /// Actions that disrupt a player or hand out goods must be confirmed.
+    fn requires_confirmation(self) -> bool {
+        matches!(self, Self::Kick | Self::Teleport | Self::SendItems | Self::SendMoney | Self::Mute)
+    }
+
+    /// Whether a role may run this action. Admins may run everything; the
+    /// other staff roles use `GM_ACTIONS_<ROLE>` (comma-separated slugs) or
+    /// the defaults below.
+    fn allowed_for(self, role: &str) -> bool {
+        let defaults: &[GmActionKind] = match role {
+            "admin" => return true,
+            "gamemaster" => &Self::ALL,
+            "moderator" => &[Self::Kick, Self::Mute, Self::Unmute],
+            _ => return false,
+        };
+        match std::env::var(format!("GM_ACTIONS_{}", role.to_uppercase())) {
+            Ok(list) => list.split(',').any(|s| s.trim() == self.slug()),
+            Err(_) => defaults.contains(&self),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MailItem {
+    pub entry: u32,
+    #[serde(default = "one")]
+    pub count: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// Typed parameters, keyed by action slug.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", content = "params", rename_all = "kebab-case")]
+enum GmAction {
+    Kick { player: String, reason: Option<String> },
+    Teleport { player: String, location: String },
+    SendItems { player: String, subject: String, body: Option<String>, items: Vec<MailItem> },
+    SendMoney { player: String, subject: String, body: Option<String>, copper: u64 },
+    Revive { player: String },
+    Mute { player: String, minutes: u32, reason: Option<String> },
+    Unmute { player: String },
+}
+
+fn check_player(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.len() < 2 || name.len() > 12 || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err("Player must be a character name".to_string());
+    }
+    Ok(name)
+}
+
+/// Free text placed inside a console command. Quotes and newlines would let
+/// it break out of its argument.
+fn check_text<'a>(label: &str, text: &'a str, max: usize) -> Result<&'a str, String> {
+    let text = text.trim();
+    if text.len() > max || text.contains(['"', '\r', '\n']) {
+        return Err(format!("{} must be at most {} characters, without quotes or line breaks", label, max));
+    }
+    Ok(text)
+}
+
+impl GmAction {
+    /// Validates the parameters and builds the console command.
+    fn command(&self) -> Result<String, String> {
+        Ok(match self {
+            Self::Kick { player, reason } => {
+                let player = check_player(player)?;
+                match reason.as_deref().map(|r| check_text("Reason", r, 100)).transpose()? {
+                    Some(reason) if !reason.is_empty() => format!("kick {} {}", player, reason),
+                    _ => format!("kick {}", player),
+                }
+            },
+            Self::Teleport { player, location } => {
+                let location = location.trim();
+                if location.is_empty() || location.len() > 50 || !location.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '\'') {
+                    return Err("Location must be a teleport location name".to_string());
+                }
+                format!("tele name {} {}", check_player(player)?, location)
+            },
+            Self::SendItems { player, subject, body, items } => {
+                if items.is_empty() || items.len() > MAX_MAIL_ITEMS {
+                    return Err(format!("Send between 1 and {} items", MAX_MAIL_ITEMS));
+                }
+                if items.iter().any(|i| i.entry == 0 || i.count == 0 || i.count > 1000) {
+                    return Err("Items need an entry and a count between 1 and 1000".to_string());
+                }
+                let items: Vec<String> = items.iter().map(|i| format!("{}:{}", i.entry, i.count)).collect();
+                format!(
+                    "send items {} \"{}\" \"{}\" {}",
+                    check_player(player)?,
+                    mail_subject(subject)?,
+                    check_text("Body", body.as_deref().unwrap_or(""), 500)?,
+                    items.join(" "),
+                )
+            },
+            Self::SendMoney { player, subject, body, copper } => {
+                if *copper == 0 || *copper > max_mail_copper() {
+                    return Err(format!("Copper must be between 1 and {}", max_mail_copper()));
+                }
+                format!(
+                    "send money {} \"{}\" \"{}\" {}",
+                    check_player(player)?,
+                    mail_subject(subject)?,
+                    check_text("Body", body.as_deref().unwrap_or(""), 500)?,
+                    copper,
+                )
+            },
+            Self::Revive { player } => format!("revive {}", check_player(player)?),
+            Self::Mute { player, minutes, reason } => {
+                if *minutes == 0 || *minutes > MAX_MUTE_MINUTES {
+                    return Err(format!("Minutes must be between 1 and {}", MAX_MUTE_MINUTES));
+                }
+                let player = check_player(player)?;
+                match reason.as_deref().map(|r| check_text("Reason", r, 100)).transpose()? {
+                    Some(reason) if !reason.is_empty() => format!("mute {} {} {}", player, minutes, reason),
+                    _ => format!("mute {} {}", player, minutes),
+                }
+            },
+            Self::Unmute { player } => format!("unmute {}", check_player(player)?),
+        })
+    }
+}
+
+fn mail_subject(subject: &str) -> Result<&str, String> {
+    let subject = check_text("Subject", subject, 100)?;
+    if subject.is_empty() {
+        return Err("Subject is required".to_string());
+    }
+    Ok(subject)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GmActionRequest {
+    pub action: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+    /// Token from the 428 response, for actions that require confirmation.
+    #[serde(rename = "confirmationToken")]
+    pub confirmation_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleRequest {
+    pub email: String,
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub action: Option<String>,
+    #[serde(rename = "requestedBy")]
+    pub requested_by: Option<String>,
+    pub outcome: Option<String>,
+    pub limit: Option<i64>,
+}
+
+async fn audit(state: &AppState, claims: &Claims, action: &str, params: &serde_json::Value, command: Option<String>, outcome: &str, output: Option<String>) {
+    let entry = GmAuditEntry {
+        id: None,
+        action: action.to_string(),
+        params: mongodb::bson::to_bson(params).unwrap_or(Bson::Null),
+        command,
+        outcome: outcome.to_string(),
+        output,
+        requested_by: claims.sub.clone(),
+        role: claims.role.clone(),
+        at: now_secs(),
+    };
+    let logs: Collection<GmAuditEntry> = state.mongo.collection("gm_audit_log");
+    if let Err(e) = logs.insert_one(entry, None).await {
+        tracing::error!("Failed to record GM action {}: {}", action, e);
+    }
+}
+
+fn confirmations(state: &AppState) -> Collection<GmConfirmation> {
+    state.mongo.collection("gm_confirmations")
+}
+
+/// Stores a single-use token that lets the caller run exactly this command
+/// within `CONFIRMATION_TTL_SECS`.
+async fn issue_confirmation(state: &AppState, claims: &Claims, action: &str, command: &str) -> Result<String, mongodb::error::Error> {
+    let now = now_secs();
+    confirmations(state).delete_many(doc! { "expiresAt": { "$lte": now } }, None).await?;
+
+    let token: String = rand::thread_rng().sample_iter(&Alphanumeric).take(32).map(char::from).collect();
+    confirmations(state).insert_one(GmConfirmation {
+        token: token.clone(),
+        action: action.to_string(),
+        command: command.to_string(),
+        requested_by: claims.sub.clone(),
+        expires_at: now + CONFIRMATION_TTL_SECS,
+    }, None).await?;
+    Ok(token)
+}
+
+/// Consumes a confirmation token; only valid for the user and the exact
+/// command it was issued for.
+async fn redeem_confirmation(state: &AppState, claims: &Claims, token: &str, command: &str) -> Result<bool, mongodb::error::Error> {
+    let confirmation = confirmations(state).find_one_and_delete(doc! {
+        "token": token,
+        "requestedBy": &claims.sub,
+        "command": command,
+        "expiresAt": { "$gt": now_secs() },
+    }, None).await?;
+    Ok(confirmation.is_some())
+}
+
+/// Actions the caller's role may run, with their parameters.
+pub async fn list_actions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let mut claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    claims.role = match current_role(&state, &claims).await {
+        Ok(role) => role,
+        Err(e) => return e.into_response(),
+    };
+    if !claims.is_staff() {
+        return (StatusCode::FORBIDDEN, "Staff access required").into_response();
+    }
+
+    let actions: Vec<serde_json::Value> = GmActionKind::ALL.into_iter()
+        .filter(|a| a.allowed_for(&claims.role))
+        .map(|a| serde_json::json!({
+            "action": a.slug(),
+            "params": a.params().iter().map(|(name, kind)| serde_json::json!({ "name": name, "type": kind })).collect::<Vec<_>>(),
+            "requiresConfirmation": a.requires_confirmation(),
+        }))
+        .collect();
+    Json(actions).into_response()
+}
+
+pub async fn run_action(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<GmActionRequest>,
+) -> impl IntoResponse {
+    let mut claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    claims.role = match current_role(&state, &claims).await {
+        Ok(role) => role,
+        Err(e) => return e.into_response(),
+    };
+    if !claims.is_staff() {
+        return (StatusCode::FORBIDDEN, "Staff access required").into_response();
+    }
+
+    let Some(kind) = GmActionKind::from_slug(&payload.action) else {
+        audit(&state, &claims, &payload.action, &payload.params, None, "invalid", Some("Unknown GM action".to_string())).await;
+        return (StatusCode::BAD_REQUEST, "Unknown GM action").into_response();
+    };
+    if !kind.allowed_for(&claims.role) {
+        audit(&state, &claims, kind.slug(), &payload.params, None, "denied", None).await;
+        return (StatusCode::FORBIDDEN, "Your role may not run this action").into_response();
+    }
+
+    let command = serde_json::from_value::<GmAction>(serde_json::json!({
+        "action": kind.slug(),
+        "params": payload.params,
+    }))
+    .map_err(|e| format!("Invalid parameters: {}", e))
+    .and_then(|action| action.command());
+    let command = match command {
+        Ok(c) => c,
+        Err(e) => {
+            audit(&state, &claims, kind.slug(), &payload.params, None, "invalid", Some(e.clone())).await;
+            return (StatusCode::BAD_REQUEST, e).into_response();
+        }
+    };
+
+    if kind.requires_confirmation() {
+        match payload.confirmation_token.as_deref() {
+            None => {
+                let token = match issue_confirmation(&state, &claims, kind.slug(), &command).await {
+                    Ok(t) => t,
+                    Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+                };
+                audit(&state, &claims, kind.slug(), &payload.params, Some(command.clone()), "confirmation-required", None).await;
+                return (StatusCode::PRECONDITION_REQUIRED, Json(serde_json::json!({
+                    "confirmationRequired": true,
+                    "action": kind.slug(),
+                    "command": command,
+                    "confirmationToken": token,
+                    "expiresIn": CONFIRMATION_TTL_SECS,
+                }))).into_response();
+            },
+            Some(token) => match redeem_confirmation(&state, &claims, token, &command).await {
+                Ok(true) => {},
+                Ok(false) => {
+                    audit(&state, &claims, kind.slug(), &payload.params, Some(command), "invalid", Some("Invalid confirmation token".to_string())).await;
+                    return (StatusCode::BAD_REQUEST, "Confirmation token is invalid, expired or for a different command").into_response();
+                },
+                Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+            },
+        }
+    }
+
+    match state.soap.execute(&command).await {
+        Ok(output) => {
+            tracing::info!("{} {} ran GM action: {}", claims.role, claims.sub, command);
+            audit(&state, &claims, kind.slug(), &payload.params, Some(command.clone()), "success", Some(output.clone())).await;
+            Json(serde_json::json!({
+                "action": kind.slug(),
+                "command": command,
+                "lines": output_lines(&output),
+            })).into_response()
+        },
+        Err(e) => {
+            tracing::warn!("GM action '{}' by {} failed: {}", command, claims.sub, e);
+            audit(&state, &claims, kind.slug(), &payload.params, Some(command), "failed", Some(e.to_string())).await;
+            e.into_response()
+        }
+    }
+}
+
+pub async fn list_audit(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<AuditQuery>,
+) -> impl IntoResponse {
+    let mut claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    claims.role = match current_role(&state, &claims).await {
+        Ok(role) => role,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let mut filter = Document::new();
+    if let Some(action) = params.action {
+        filter.insert("action", action);
+    }
+    if let Some(user) = params.requested_by {
+        filter.insert("requestedBy", user);
+    }
+    if let Some(outcome) = params.outcome {
+        filter.insert("outcome", outcome);
+    }
+    let limit = params.limit.unwrap_or(100).clamp(1, 500);
+
+    let logs: Collection<GmAuditEntry> = state.mongo.collection("gm_audit_log");
+    let cursor = match logs.find(filter, FindOptions::builder().sort(doc! { "at": -1 }).limit(limit).build()).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    match cursor.try_collect::<Vec<GmAuditEntry>>().await {
+        Ok(entries) => {
+            let entries: Vec<serde_json::Value> = entries.into_iter().map(|e| serde_json::json!({
+                "id": e.id.map(|id| id.to_hex()),
+                "action": e.action,
+                "params": e.params.into_relaxed_extjson(),
+                "command": e.command,
+                "outcome": e.outcome,
+                "output": e.output,
+                "requestedBy": e.requested_by,
+                "role": e.role,
+                "at": e.at,
+            })).collect();
+            Json(entries).into_response()
+        },
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+}
+
+/// Grants or revokes a staff role. Staff checks read the stored role, so the
+/// change applies to the user's current session too.
+pub async fn set_user_role(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<RoleRequest>,
+) -> impl IntoResponse {
+    let mut claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    claims.role = match current_role(&state, &claims).await {
+        Ok(role) => role,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+    if !ROLES.contains(&payload.role.as_str()) {
+        return (StatusCode::BAD_REQUEST, "Role must be user, moderator, gamemaster or admin").into_response();
+    }
+
+    let users: Collection<User> = state.mongo.collection("users");
+    let user = match users.find_one(doc! { "email": &payload.email }, None).await {
+        Ok(Some(u)) => u,
+        Ok(None) => return (StatusCode::NOT_FOUND, "User not found").into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+    let Some(id) = user.id else {
+        return (StatusCode::NOT_FOUND, "User not found").into_response();
+    };
+    if id.to_hex() == claims.sub {
+        return (StatusCode::BAD_REQUEST, "You cannot change your own role").into_response();
+    }
+
+    if users.update_one(doc! { "_id": id }, doc! { "$set": { "role": &payload.role } }, None).await.is_err() {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+    let params = serde_json::json!({ "email": &payload.email, "from": &user.role, "to": &payload.role });
+    audit(&state, &claims, "set-role", &params, None, "success", None).await;
+    tracing::info!("Admin {} changed role of {} from {} to {}", claims.sub, payload.email, user.role, payload.role);
+
+    Json(serde_json::json!({
+        "id": id.to_hex(),
+        "nickname": user.nickname,
+        "email": user.email,
+        "role": payload.role,
+    })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(request: serde_json::Value) -> Result<String, String> {
+        serde_json::from_value::<GmAction>(request).unwrap().command()
+    }
+
+    #[test]
+    fn builds_console_commands() {
+        assert_eq!(command(serde_json::json!({ "action": "kick", "params": { "player": " Arthas " } })), Ok("kick Arthas".to_string()));
+        assert_eq!(
+            command(serde_json::json!({ "action": "mute", "params": { "player": "Arthas", "minutes": 30, "reason": "spam" } })),
+            Ok("mute Arthas 30 spam".to_string()),
+        );
+        assert_eq!(
+            command(serde_json::json!({ "action": "teleport", "params": { "player": "Arthas", "location": "Orgrimmar" } })),
+            Ok("tele name Arthas Orgrimmar".to_string()),
+        );
+        assert_eq!(
+            command(serde_json::json!({
+                "action": "send-items",
+                "params": { "player": "Arthas", "subject": "Refund", "items": [{ "entry": 6948 }, { "entry": 2589, "count": 20 }] },
+            })),
+            Ok("send items Arthas \"Refund\" \"\" 6948:1 2589:20".to_string()),
+        );
+    }
+
+    #[test]
+    fn rejects_text_that_breaks_out_of_its_argument() {
+        assert!(command(serde_json::json!({ "action": "kick", "params": { "player": "Arthas", "reason": "a\nserver shutdown 1" } })).is_err());
+        assert!(command(serde_json::json!({
+            "action": "send-money",
+            "params": { "player": "Arthas", "subject": "x\" \"y", "copper": 100 },
+        })).is_err());
+        assert!(command(serde_json::json!({ "action": "revive", "params": { "player": "Arthas;kick" } })).is_err());
+        assert!(command(serde_json::json!({ "action": "teleport", "params": { "player": "Arthas", "location": "a b" } })).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_amounts() {
+        assert!(command(serde_json::json!({ "action": "mute", "params": { "player": "Arthas", "minutes": 0 } })).is_err());
+        assert!(command(serde_json::json!({ "action": "mute", "params": { "player": "Arthas", "minutes": MAX_MUTE_MINUTES + 1 } })).is_err());
+        assert!(command(serde_json::json!({ "action": "send-items", "params": { "player": "Arthas", "subject": "Gift", "items": [] } })).is_err());
+        assert!(command(serde_json::json!({
+            "action": "send-items",
+            "params": { "player": "Arthas", "subject": "Gift", "items": [{ "entry": 6948, "count": 1001 }] },
+        })).is_err());
+        assert!(command(serde_json::json!({ "action": "send-money", "params": { "player": "Arthas", "subject": "Gift", "copper": 0 } })).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, to_document},
+    options::{FindOptions, IndexOptions, UpdateOptions},
+    Collection, IndexModel,
+};
+use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder, Row};
+use std::collections::BTreeMap;
+
+use crate::{
+    AppState,
+    clock::{interval_from_env, now_secs},
+    domain::{CharacterTraits, Locale},
+    models::{HallOfFameCharacter, HallOfFameEntry},
+    realms::RealmDb,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct HallOfFameQuery {
+    pub kind: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Starts the background task that records first completions of tracked
+/// achievements on every realm. `HALL_OF_FAME_INTERVAL_SECS=0` disables it.
+pub fn spawn_watcher(state: AppState) {
+    let Some(period) = interval_from_env("HALL_OF_FAME_INTERVAL_SECS", 60) else {
+        tracing::info!("Hall of fame watcher disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let collection: Collection<HallOfFameEntry> = state.mongo.collection("hall_of_fame");
+        let index = IndexModel::builder()
+            .keys(doc! { "realm": 1, "achievementId": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        if let Err(e) = collection.create_index(index, None).await {
+            tracing::warn!("Failed to create hall_of_fame index: {}", e);
+        }
+
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            for (realm, pool) in state.realms.all() {
+                if let Err(e) = scan(&state, realm, &pool).await {
+                    tracing::warn!("Hall of fame scan failed for realm {}: {}", realm, e);
+                }
+            }
+        }
+    });
+}
+
+async fn scan(state: &AppState, realm: u32, pool: &sqlx::MySqlPool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let tracked: Vec<(u32, String, bool)> = state.achievements
+        .hall_of_fame()
+        .map(|m| (m.id, m.name.clone(), m.realm_first))
+        .collect();
+
+    if tracked.is_empty() {
+        return Ok(());
+    }
+
+    // Everyone who completed a tracked achievement at its earliest recorded time,
+    // so a server-first boss kill credits the whole raid.
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT ca.achievement, ca.date, c.name, c.race, c.class, c.gender \
+         FROM character_achievement ca \
+         JOIN characters c ON c.guid = ca.guid \
+         JOIN (SELECT achievement, MIN(date) AS firstDate FROM character_achievement WHERE achievement IN (",
+    );
+    let mut separated = builder.separated(", ");
+    for (id, _, _) in &tracked {
+        separated.push_bind(*id);
+    }
+    builder.push(") GROUP BY achievement) f ON f.achievement = ca.achievement AND f.firstDate = ca.date");
+
+    let rows = builder.build().fetch_all(pool).await?;
+
+    let mut firsts: BTreeMap<u32, (i64, Vec<HallOfFameCharacter>)> = BTreeMap::new();
+    for row in &rows {
+        let achievement = row.try_get::<u16, _>("achievement").unwrap_or_default() as u32;
+        let date = row.try_get::<u32, _>("date").unwrap_or_default() as i64;
+        let entry = firsts.entry(achievement).or_insert_with(|| (date, Vec::new()));
+        entry.1.push(HallOfFameCharacter {
+            name: row.try_get::<String, _>("name").unwrap_or_default(),
+            traits: CharacterTraits::new(
+                row.try_get::<u8, _>("race").unwrap_or_default(),
+                row.try_get::<u8, _>("class").unwrap_or_default(),
+                row.try_get::<u8, _>("gender").ok(),
+                Locale::En,
+            ),
+        });
+    }
+
+    let collection: Collection<HallOfFameEntry> = state.mongo.collection("hall_of_fame");
+    let now = now_secs();
+
+    for (id, title, realm_first) in tracked {
+        let Some((date, characters)) = firsts.remove(&id) else {
+            continue;
+        };
+
+        let entry = HallOfFameEntry {
+            id: None,
+            realm,
+            achievement_id: id,
+            title,
+            kind: if realm_first { "realm_first" } else { "first_kill" }.to_string(),
+            date,
+            characters,
+            recorded_at: now,
+        };
+
+        // $setOnInsert keeps the original record if the entry already exists.
+        let result = collection.update_one(
+            doc! { "realm": realm, "achievementId": id },
+            doc! { "$setOnInsert": to_document(&entry)? },
+            UpdateOptions::builder().upsert(true).build(),
+        ).await?;
+
+        if result.upserted_id.is_some() {
+            announce(&entry).await;
+        }
+    }
+
+    Ok(())
+}
+
+async fn announce(entry: &HallOfFameEntry) {
+    let names: Vec<&str> = entry.characters.iter().map(|c| c.name.as_str()).collect();
+    let message = match entry.kind.as_str() {
+        "realm_first" => format!("{} has earned {}", names.join(", "), entry.title),
+        _ => format!("Server first! {} completed {}", names.join(", "), entry.title),
+    };
+    tracing::info!("Hall of fame: {}", message);
+
+    let webhook = std::env::var("HALL_OF_FAME_WEBHOOK_URL").unwrap_or_default();
+    if webhook.is_empty() {
+        return;
+    }
+
+    let client = reqwest::Client::new();
+    match client.post(&webhook).json(&serde_json::json!({ "content": message })).send().await {
+        Ok(resp) if !resp.status().is_success() => {
+            tracing::warn!("Hall of fame webhook returned {}", resp.status());
+        },
+        Ok(_) => {},
+        Err(e) => tracing::warn!("Failed to call hall of fame webhook: {}", e),
+    }
+}
+
+pub async fn get_hall_of_fame(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Query(params): Query<HallOfFameQuery>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+    let collection: Collection<HallOfFameEntry> = state.mongo.collection("hall_of_fame");
+
+    let filter = match params.kind.as_deref() {
+        Some(kind @ ("realm_first" | "first_kill")) => doc! { "realm": realm.id, "kind": kind },
+        Some(_) => return (StatusCode::BAD_REQUEST, "Kind must be realm_first or first_kill").into_response(),
+        None => doc! { "realm": realm.id },
+    };
+    let options = FindOptions::builder()
+        .sort(doc! { "date": -1 })
+        .limit(params.limit.unwrap_or(50).clamp(1, 200))
+        .build();
+
+    let cursor = match collection.find(filter, options).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    match cursor.try_collect::<Vec<HallOfFameEntry>>().await {
+        Ok(mut entries) => {
+            // Names are stored with the entry; re-resolve them for the caller's language.
+            for character in entries.iter_mut().flat_map(|e| e.characters.iter_mut()) {
+                let traits = &character.traits;
+                character.traits = CharacterTraits::new(traits.race, traits.class, traits.gender, locale);
+            }
+            Json(entries).into_response()
+        },
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+}
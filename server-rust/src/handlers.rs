@@ -3,8 +3,8 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
-use crate::{AppState, models::{User, CreateUserRequest, LoginRequest, LoginResponse, UserResponse, GoogleLoginRequest, LoginGameRequest}};
-use mongodb::{bson::doc, bson::oid::ObjectId, Collection};
+use crate::{AppState, realms::RealmDb, domain::{CharacterTraits, Locale}, models::{CharacterSummary, User, CreateUserRequest, LoginRequest, LoginResponse, UserResponse, GoogleLoginRequest, LoginGameRequest, LinkAccountRequest}};
+use mongodb::{bson::doc, bson::oid::ObjectId, error::{ErrorKind, WriteFailure}, options::IndexOptions, Collection, IndexModel};
 use bcrypt::{hash, verify, DEFAULT_COST};
 use jsonwebtoken::{encode, Header, EncodingKey};
 use serde::{Deserialize, Serialize};
@@ -17,10 +17,106 @@ use lettre::{Message, SmtpTransport, Transport};
 use lettre::transport::smtp::authentication::Credentials;
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
+pub(crate) struct Claims {
+    pub(crate) sub: String,
     exp: usize,
-    role: String,
+    pub(crate) role: String,
+}
+
+/// Roles an admin can assign, from least to most privileged.
+pub(crate) const ROLES: &[&str] = &["user", "moderator", "gamemaster", "admin"];
+
+impl Claims {
+    /// Staff roles that handle player support.
+    pub(crate) fn is_staff(&self) -> bool {
+        matches!(self.role.as_str(), "admin" | "gamemaster" | "moderator")
+    }
+}
+
+/// MongoDB duplicate key error.
+const DUPLICATE_KEY: i32 = 11000;
+
+pub(crate) fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    matches!(&*error.kind, ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == DUPLICATE_KEY)
+}
+
+/// Indexes on `users`. A game account can be linked to one user only; the
+/// index enforces it for concurrent links, which a lookup can't. Users
+/// without links store an empty list and are left out of it.
+pub async fn ensure_user_indexes(mongo: &mongodb::Database) -> Result<(), mongodb::error::Error> {
+    let collection: Collection<User> = mongo.collection("users");
+    let index = IndexModel::builder()
+        .keys(doc! { "linkedAccounts": 1 })
+        .options(IndexOptions::builder()
+            .name("linkedAccounts_unique".to_string())
+            .unique(true)
+            .partial_filter_expression(doc! { "linkedAccounts.0": { "$exists": true } })
+            .build())
+        .build();
+    collection.create_index(index, None).await?;
+    Ok(())
+}
+
+/// Decodes the bearer token from the request, for handlers outside this module.
+pub(crate) fn authenticate(headers: &axum::http::HeaderMap) -> Result<Claims, (StatusCode, &'static str)> {
+    let token = match headers.get("Authorization") {
+        Some(value) => value.to_str().unwrap_or("").replace("Bearer ", ""),
+        None => return Err((StatusCode::UNAUTHORIZED, "Missing token")),
+    };
+
+    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
+    match jsonwebtoken::decode::<Claims>(
+        &token,
+        &jsonwebtoken::DecodingKey::from_secret(secret.as_bytes()),
+        &jsonwebtoken::Validation::default(),
+    ) {
+        Ok(data) => Ok(data.claims),
+        Err(_) => Err((StatusCode::UNAUTHORIZED, "Invalid token")),
+    }
+}
+
+/// Game account ids the caller may act on. Dashboard tokens carry the Mongo
+/// user id, game logins carry the account id directly.
+pub(crate) async fn caller_game_accounts(state: &AppState, claims: &Claims) -> Result<Vec<u32>, (StatusCode, &'static str)> {
+    if let Ok(account_id) = claims.sub.parse::<u32>() {
+        return Ok(vec![account_id]);
+    }
+
+    let oid = match ObjectId::parse_str(&claims.sub) {
+        Ok(oid) => oid,
+        Err(_) => return Err((StatusCode::BAD_REQUEST, "Invalid user ID")),
+    };
+
+    let collection: Collection<User> = state.mongo.collection("users");
+    match collection.find_one(doc! { "_id": oid }, None).await {
+        Ok(Some(user)) => {
+            let mut accounts: Vec<u32> = user.game_id.into_iter().collect();
+            for account in user.linked_accounts {
+                if !accounts.contains(&account) {
+                    accounts.push(account);
+                }
+            }
+            Ok(accounts)
+        },
+        Ok(None) => Err((StatusCode::NOT_FOUND, "User not found")),
+        Err(_) => Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error")),
+    }
+}
+
+/// The caller's role as stored now. Tokens stay valid for days, so checks
+/// that grant staff powers use this rather than the role in the token.
+/// Game logins are always plain users.
+pub(crate) async fn current_role(state: &AppState, claims: &Claims) -> Result<String, (StatusCode, &'static str)> {
+    let Ok(oid) = ObjectId::parse_str(&claims.sub) else {
+        return Ok("user".to_string());
+    };
+
+    let collection: Collection<User> = state.mongo.collection("users");
+    match collection.find_one(doc! { "_id": oid }, None).await {
+        Ok(Some(user)) => Ok(user.role),
+        Ok(None) => Err((StatusCode::NOT_FOUND, "User not found")),
+        Err(_) => Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error")),
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -51,6 +147,19 @@ fn generate_random_password() -> String {
 }
 
 async fn send_game_password_email(email: &str, username: &str, password: &str) -> Result<(), String> {
+    send_email(
+        email,
+        "Welcome to Aethelgard WoW!",
+        format!(
+            "Welcome, Hero!\n\nYour account has been created successfully.\n\nGame Username: {}\nGame Password: {}\n\nRealmlist: set realmlist game.aethelgard-wow.com\n\nSee you in Azeroth!",
+            username, password
+        ),
+    ).await
+}
+
+/// Sends a plain-text email through the configured SMTP relay. Without SMTP
+/// credentials the message is only logged.
+pub(crate) async fn send_email(email: &str, subject: &str, body: String) -> Result<(), String> {
     let smtp_host = std::env::var("SMTP_HOST").unwrap_or_else(|_| "smtp.gmail.com".to_string());
     let smtp_user = std::env::var("SMTP_USER").unwrap_or_default();
     let smtp_pass = std::env::var("SMTP_PASS").unwrap_or_default();
@@ -58,18 +167,15 @@ async fn send_game_password_email(email: &str, username: &str, password: &str) -
 
     if smtp_user.is_empty() || smtp_pass.is_empty() {
         tracing::warn!("SMTP credentials not set. Skipping email sending for {}", email);
-        tracing::info!("Mock Email - To: {}, User: {}, Pass: {}", email, username, password);
+        tracing::info!("Mock Email - To: {}, Subject: {}\n{}", email, subject, body);
         return Ok(());
     }
 
     let email_content = Message::builder()
         .from(smtp_from.parse().map_err(|e: lettre::address::AddressError| e.to_string())?)
         .to(email.parse().map_err(|e: lettre::address::AddressError| e.to_string())?)
-        .subject("Welcome to Aethelgard WoW!")
-        .body(format!(
-            "Welcome, Hero!\n\nYour account has been created successfully.\n\nGame Username: {}\nGame Password: {}\n\nRealmlist: set realmlist game.aethelgard-wow.com\n\nSee you in Azeroth!",
-            username, password
-        ))
+        .subject(subject)
+        .body(body)
         .map_err(|e| e.to_string())?;
 
     let creds = Credentials::new(smtp_user, smtp_pass);
@@ -104,7 +210,7 @@ pub async fn check_username(
 ) -> impl IntoResponse {
     let query = "SELECT count(*) as count FROM account WHERE username = ?";
     let count: i64 = match sqlx::query_scalar(query)
-        .bind(&payload.username.to_uppercase())
+        .bind(payload.username.to_uppercase())
         .fetch_one(&state.mysql_auth)
         .await {
             Ok(c) => c,
@@ -118,6 +224,10 @@ pub async fn signup(
     State(state): State<AppState>,
     Json(payload): Json<CreateUserRequest>,
 ) -> impl IntoResponse {
+    if let Some(mode) = crate::maintenance::current(&state).await {
+        return crate::maintenance::unavailable(&mode);
+    }
+
     let collection: Collection<User> = state.mongo.collection("users");
 
     // Check if user exists in Mongo
@@ -190,6 +300,7 @@ pub async fn signup(
         avatar_url: payload.avatar_url,
         role: "user".to_string(),
         game_id: game_account_id,
+        linked_accounts: Vec::new(),
         created_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64,
     };
 
@@ -293,6 +404,11 @@ pub async fn login_google(
              }
         },
         Ok(None) => {
+            // New users get a game account, which is closed during maintenance.
+            if let Some(mode) = crate::maintenance::current(&state).await {
+                return crate::maintenance::unavailable(&mode);
+            }
+
             // Create new user logic
             
             // 1. Generate Game Account Credentials
@@ -334,9 +450,9 @@ pub async fn login_google(
             }
 
 
-            let nickname = google_user.name.clone().unwrap_or_else(|| safe_prefix);
+            let nickname = google_user.name.clone().unwrap_or(safe_prefix);
             let first_name = google_user.given_name.unwrap_or_else(|| "User".to_string());
-            let last_name = google_user.family_name.unwrap_or_else(|| "".to_string());
+            let last_name = google_user.family_name.unwrap_or_default();
             
             let role = if google_user.email == "andersonjsilva@outlook.com" {
                 "admin".to_string()
@@ -354,6 +470,7 @@ pub async fn login_google(
                 avatar_url: google_user.picture.clone(),
                 role,
                 game_id: game_account_id,
+                linked_accounts: Vec::new(),
                 created_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64,
             };
 
@@ -374,6 +491,7 @@ pub async fn login_google(
                 avatar_url: google_user.picture,
                 role: "user".to_string(),
                 game_id: game_account_id,
+                linked_accounts: Vec::new(),
                 created_at: 0,
             }
         },
@@ -407,7 +525,7 @@ pub async fn login_google(
             email: user.email,
             first_name: Some(user.first_name),
             last_name: Some(user.last_name),
-            avatar_url: google_user.picture,
+            avatar_url: user.avatar_url,
             is_admin: user.role == "admin",
             game_id: user.game_id,
         },
@@ -456,26 +574,32 @@ pub async fn me(
 }
 
 pub async fn list_characters(
-    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: axum::http::HeaderMap,
 ) -> impl IntoResponse {
     // This is just a test endpoint to verify MySQL connection
     // In real app, we would filter by user account
-    let query = "SELECT name, race, class, level FROM characters LIMIT 10";
+    let query = "SELECT name, race, class, gender, level FROM characters LIMIT 10";
+    let locale = Locale::from_headers(&headers);
     
     let rows = match sqlx::query(query)
-        .fetch_all(&state.mysql_char)
+        .fetch_all(&realm.pool)
         .await {
             Ok(rows) => rows,
             Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("MySQL error: {}", e)).into_response(),
         };
 
-    let characters: Vec<serde_json::Value> = rows.iter().map(|row| {
-        serde_json::json!({
-            "name": row.try_get::<String, _>("name").unwrap_or_default(),
-            "race": row.try_get::<u8, _>("race").unwrap_or_default(),
-            "class": row.try_get::<u8, _>("class").unwrap_or_default(),
-            "level": row.try_get::<u8, _>("level").unwrap_or_default(),
-        })
+    let characters: Vec<CharacterSummary> = rows.iter().map(|row| {
+        CharacterSummary {
+            name: row.try_get::<String, _>("name").unwrap_or_default(),
+            traits: CharacterTraits::new(
+                row.try_get::<u8, _>("race").unwrap_or_default(),
+                row.try_get::<u8, _>("class").unwrap_or_default(),
+                row.try_get::<u8, _>("gender").ok(),
+                locale,
+            ),
+            level: row.try_get::<u8, _>("level").unwrap_or_default(),
+        }
     }).collect();
 
     Json(characters).into_response()
@@ -558,7 +682,7 @@ pub async fn update_profile(
         return (StatusCode::OK, "No changes").into_response();
     }
 
-    if let Err(_) = collection.update_one(doc! { "_id": oid }, doc! { "$set": update_doc }, None).await {
+    if collection.update_one(doc! { "_id": oid }, doc! { "$set": update_doc }, None).await.is_err() {
         return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update user").into_response();
     }
 
@@ -599,11 +723,11 @@ pub async fn get_server_config(
 ) -> impl IntoResponse {
     let collection: Collection<crate::models::ServerConfig> = state.mongo.collection("server_config");
     
-    match collection.find_one(doc! {}, None).await {
-        Ok(Some(config)) => Json(config).into_response(),
+    let config = match collection.find_one(doc! {}, None).await {
+        Ok(Some(config)) => config,
         Ok(None) => {
             // Return default config
-            Json(crate::models::ServerConfig {
+            crate::models::ServerConfig {
                 id: None,
                 server_name: "Aethelgard WoW".to_string(),
                 realmlist: "game.aethelgard-wow.com".to_string(),
@@ -613,10 +737,17 @@ pub async fn get_server_config(
                 gold_rate: 1.0,
                 rep_rate: 1.0,
                 motd: Some("Welcome to the server!".to_string()),
-            }).into_response()
+            }
         },
-        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    // The maintenance banner is added here rather than stored with the config.
+    let mut body = serde_json::to_value(&config).unwrap_or_default();
+    if let Some(mode) = crate::maintenance::current(&state).await {
+        body["maintenance"] = crate::maintenance::banner(&mode);
     }
+    Json(body).into_response()
 }
 
 pub async fn update_server_config(
@@ -644,39 +775,105 @@ pub async fn update_server_config(
         return (StatusCode::FORBIDDEN, "Admin access required").into_response();
     }
 
-    let collection: Collection<crate::models::ServerConfig> = state.mongo.collection("server_config");
-    
-    // Update or Insert
-    // We assume there's only one config doc
-    let count = collection.count_documents(doc! {}, None).await.unwrap_or(0);
-    
-    if count == 0 {
-        match collection.insert_one(payload, None).await {
-            Ok(_) => (StatusCode::OK, "Config created").into_response(),
-            Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create config").into_response(),
+    if let Err(e) = crate::config_sync::validate_rates(&payload) {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    if let Some(motd) = &payload.motd {
+        if let Err(e) = crate::announcements::validate_motd(motd) {
+            return (StatusCode::BAD_REQUEST, e).into_response();
         }
-    } else {
-        // Update first found
-        let update_doc = doc! {
-            "$set": {
-                "serverName": payload.server_name,
-                "realmlist": payload.realmlist,
-                "expansion": payload.expansion,
-                "xpRate": payload.xp_rate,
-                "dropRate": payload.drop_rate,
-                "goldRate": payload.gold_rate,
-                "repRate": payload.rep_rate,
-                "motd": payload.motd,
+    }
+
+    let collection: Collection<crate::models::ServerConfig> = state.mongo.collection("server_config");
+    let previous = match collection.find_one(doc! {}, None).await {
+        Ok(p) => p,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update config").into_response(),
+    };
+
+    // The MOTD goes out first so a failure there changes nothing else; the
+    // previous text is put back if the config can't be stored after it.
+    // An empty MOTD is pushed too, so clearing it in the dashboard clears it
+    // in game.
+    if let Some(motd) = payload.motd.as_deref() {
+        match crate::announcements::push_motd(&state, motd).await {
+            Ok(live) => tracing::info!("MOTD stored in the auth database (applied live: {})", live),
+            Err(e) => {
+                tracing::error!("Failed to store MOTD in the auth database: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to push the MOTD to the realm").into_response();
             }
+        }
+    }
+
+    if save_server_config(&collection, &payload, previous.is_none()).await.is_err() {
+        restore_motd(&state, &payload, previous.as_ref()).await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update config").into_response();
+    }
+
+    // Mongo is written first and put back if worldserver.conf can't be
+    // updated, so the dashboard never advertises rates the game isn't using.
+    if let Err(e) = crate::config_sync::apply_after_update(&state, &payload).await {
+        tracing::error!("worldserver.conf sync failed: {}", e);
+        let restored = match &previous {
+            Some(config) => save_server_config(&collection, config, false).await,
+            None => collection.delete_many(doc! {}, None).await.map(|_| ()),
         };
-        
-        match collection.update_one(doc! {}, update_doc, None).await {
-            Ok(_) => (StatusCode::OK, "Config updated").into_response(),
-            Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update config").into_response(),
+        if let Err(e) = restored {
+            tracing::error!("Failed to roll back server config after sync error: {}", e);
         }
+        restore_motd(&state, &payload, previous.as_ref()).await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to apply rates to worldserver.conf").into_response();
+    }
+
+    if previous.is_none() {
+        (StatusCode::OK, "Config created").into_response()
+    } else {
+        (StatusCode::OK, "Config updated").into_response()
+    }
+}
+
+/// Puts the previous MOTD back after `update` pushed a new one but could not
+/// be stored.
+async fn restore_motd(
+    state: &AppState,
+    update: &crate::models::ServerConfig,
+    previous: Option<&crate::models::ServerConfig>,
+) {
+    if update.motd.is_none() {
+        return;
+    }
+    let motd = previous.and_then(|p| p.motd.as_deref()).unwrap_or("");
+    if let Err(e) = crate::announcements::push_motd(state, motd).await {
+        tracing::error!("Failed to restore the previous MOTD: {}", e);
     }
 }
 
+/// Inserts the single config document, or updates the first one found.
+async fn save_server_config(
+    collection: &Collection<crate::models::ServerConfig>,
+    config: &crate::models::ServerConfig,
+    create: bool,
+) -> Result<(), mongodb::error::Error> {
+    if create {
+        collection.insert_one(config, None).await?;
+        return Ok(());
+    }
+
+    let update_doc = doc! {
+        "$set": {
+            "serverName": &config.server_name,
+            "realmlist": &config.realmlist,
+            "expansion": &config.expansion,
+            "xpRate": config.xp_rate,
+            "dropRate": config.drop_rate,
+            "goldRate": config.gold_rate,
+            "repRate": config.rep_rate,
+            "motd": &config.motd,
+        }
+    };
+    collection.update_one(doc! {}, update_doc, None).await?;
+    Ok(())
+}
+
 pub async fn login_game(
     State(state): State<AppState>,
     Json(payload): Json<LoginGameRequest>,
@@ -726,3 +923,60 @@ pub async fn login_game(
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)).into_response(),
     }
 }
+
+pub async fn link_game_account(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(payload): Json<LinkAccountRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    let oid = match ObjectId::parse_str(&claims.sub) {
+        Ok(oid) => oid,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Linking requires a dashboard account").into_response(),
+    };
+
+    // Same proof of ownership as login_game: the account's own credentials.
+    let to_hash = format!("{}:{}", payload.username.to_uppercase(), payload.password.to_uppercase());
+    let mut hasher = Sha1::new();
+    hasher.update(to_hash);
+    let sha_pass_hash = hex::encode(hasher.finalize());
+
+    let account_id: u32 = match sqlx::query_scalar("SELECT id FROM account WHERE username = ? AND sha_pass_hash = ?")
+        .bind(&payload.username)
+        .bind(&sha_pass_hash)
+        .fetch_optional(&state.mysql_auth)
+        .await {
+            Ok(Some(id)) => id,
+            Ok(None) => return (StatusCode::UNAUTHORIZED, "Invalid credentials").into_response(),
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+        };
+
+    let collection: Collection<User> = state.mongo.collection("users");
+
+    // An account may only belong to one web user.
+    let filter = doc! {
+        "_id": { "$ne": oid },
+        "$or": [ { "gameId": account_id }, { "linkedAccounts": account_id } ],
+    };
+    match collection.find_one(filter, None).await {
+        Ok(Some(_)) => return (StatusCode::CONFLICT, "Account is linked to another user").into_response(),
+        Ok(None) => {},
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+
+    // The unique index settles links racing past the check above.
+    match collection.update_one(doc! { "_id": oid }, doc! { "$addToSet": { "linkedAccounts": account_id } }, None).await {
+        Ok(_) => {},
+        Err(e) if is_duplicate_key(&e) => return (StatusCode::CONFLICT, "Account is linked to another user").into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update user").into_response(),
+    }
+
+    match caller_game_accounts(&state, &claims).await {
+        Ok(accounts) => Json(serde_json::json!({ "accounts": accounts })).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
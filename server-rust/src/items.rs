@@ -0,0 +1,81 @@
+//! Item templates from the world database, shared by the auction house and
+//! the gear scores.
+
+use sqlx::{MySql, QueryBuilder, Row};
+use std::collections::HashMap;
+
+use crate::domain::ItemQuality;
+
+/// Entries per `item_template` lookup, keeping the IN list well under the
+/// prepared statement placeholder limit.
+const TEMPLATE_CHUNK: usize = 1000;
+
+pub(crate) struct ItemTemplate {
+    pub(crate) name: String,
+    pub(crate) quality: u8,
+    pub(crate) item_level: u16,
+    pub(crate) required_level: u8,
+    pub(crate) inventory_type: u8,
+}
+
+fn template_from_row(row: &sqlx::mysql::MySqlRow) -> (u32, ItemTemplate) {
+    (
+        row.try_get::<u32, _>("entry").unwrap_or_default(),
+        ItemTemplate {
+            name: row.try_get::<String, _>("name").unwrap_or_default(),
+            quality: row.try_get::<u8, _>("Quality").unwrap_or_default(),
+            item_level: row.try_get::<u16, _>("ItemLevel").unwrap_or_default(),
+            required_level: row.try_get::<u8, _>("RequiredLevel").unwrap_or_default(),
+            inventory_type: row.try_get::<u8, _>("InventoryType").unwrap_or_default(),
+        },
+    )
+}
+
+/// Escapes the LIKE wildcards in a user-supplied search term.
+pub(crate) fn like_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Templates for the given entries that match the search filters, keyed by
+/// entry.
+pub(crate) async fn search_templates(
+    pool: &sqlx::MySqlPool,
+    entries: &[u32],
+    name: Option<&str>,
+    quality: Option<ItemQuality>,
+    min_level: Option<u8>,
+    max_level: Option<u8>,
+) -> Result<HashMap<u32, ItemTemplate>, sqlx::Error> {
+    let mut templates = HashMap::new();
+    for chunk in entries.chunks(TEMPLATE_CHUNK) {
+        let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+            "SELECT entry, name, Quality, ItemLevel, RequiredLevel, InventoryType FROM item_template WHERE entry IN (",
+        );
+        let mut separated = builder.separated(", ");
+        for entry in chunk {
+            separated.push_bind(*entry);
+        }
+        builder.push(")");
+        if let Some(name) = name {
+            builder.push(" AND name LIKE ").push_bind(like_pattern(name));
+        }
+        if let Some(quality) = quality {
+            builder.push(" AND Quality = ").push_bind(quality as u8);
+        }
+        if let Some(min) = min_level {
+            builder.push(" AND RequiredLevel >= ").push_bind(min);
+        }
+        if let Some(max) = max_level {
+            builder.push(" AND RequiredLevel <= ").push_bind(max);
+        }
+
+        let rows = builder.build().fetch_all(pool).await?;
+        templates.extend(rows.iter().map(template_from_row));
+    }
+    Ok(templates)
+}
+
+pub(crate) async fn templates_by_entry(pool: &sqlx::MySqlPool, entries: &[u32]) -> Result<HashMap<u32, ItemTemplate>, sqlx::Error> {
+    search_templates(pool, entries, None, None, None, None).await
+}
@@ -1,26 +1,64 @@
 use axum::{
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
-    extract::State,
-    response::Json,
     http::Method,
+    extract::DefaultBodyLimit,
 };
 use mongodb::{Client, options::ClientOptions};
 use sqlx::mysql::MySqlPoolOptions;
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
+use tower_http::services::ServeDir;
 use dotenvy::dotenv;
 use std::env;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod models;
+mod domain;
+mod dbc;
 mod handlers;
+mod arena;
+mod armory;
+mod achievements;
+mod hall_of_fame;
+mod character_services;
+mod points;
+mod professions;
+mod portraits;
+mod ranking;
+mod progress;
+mod retention;
+mod clock;
+mod auctions;
+mod items;
+mod reputation;
+mod quests;
+mod talents;
+mod gear;
+mod soap;
+mod config_sync;
+mod status;
+mod realms;
+mod population;
+mod announcements;
+mod schedules;
+mod maintenance;
+mod tickets;
+mod gm_actions;
 
 #[derive(Clone)]
 pub struct AppState {
     pub mongo: mongodb::Database,
     pub mysql_auth: sqlx::MySqlPool,
     pub mysql_char: sqlx::MySqlPool,
+    pub realms: Arc<realms::RealmPools>,
+    pub mysql_world: sqlx::MySqlPool,
+    pub achievements: Arc<achievements::AchievementCatalog>,
+    pub talents: Arc<talents::TalentCatalog>,
+    pub factions: Arc<reputation::FactionCatalog>,
+    pub portraits: Arc<dyn portraits::PortraitStorage>,
+    pub soap: Arc<soap::SoapClient>,
+    pub status: Arc<status::StatusCache>,
 }
 
 #[tokio::main]
@@ -43,18 +81,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mysql_host = env::var("DB_HOST").unwrap_or_else(|_| "localhost".to_string());
     let db_auth = env::var("DB_AUTH").unwrap_or_else(|_| "acore_auth".to_string());
     let db_char = env::var("DB_CHAR").unwrap_or_else(|_| "characters".to_string());
+    let db_world = env::var("DB_WORLD").unwrap_or_else(|_| "acore_world".to_string());
 
     tracing::info!("Connecting to MongoDB at {}", mongo_uri);
     // MongoDB Connection
-    let mut client_options = ClientOptions::parse(&mongo_uri).await?;
+    let client_options = ClientOptions::parse(&mongo_uri).await?;
     let client = Client::with_options(client_options)?;
     let mongo_db = client.database("wow_dashboard");
     tracing::info!("MongoDB connected");
+    if let Err(e) = handlers::ensure_user_indexes(&mongo_db).await {
+        tracing::warn!("Failed to create users indexes: {}", e);
+    }
+    if let Err(e) = points::ensure_indexes(&mongo_db).await {
+        tracing::warn!("Failed to create user_points indexes: {}", e);
+    }
 
     tracing::info!("Connecting to MySQL at {}", mysql_host);
     // MySQL Connections
     let mysql_auth_url = format!("mysql://{}:{}@{}/{}", mysql_user, mysql_pass, mysql_host, db_auth);
     let mysql_char_url = format!("mysql://{}:{}@{}/{}", mysql_user, mysql_pass, mysql_host, db_char);
+    let mysql_world_url = format!("mysql://{}:{}@{}/{}", mysql_user, mysql_pass, mysql_host, db_world);
     
     let mysql_auth_pool = match MySqlPoolOptions::new()
         .max_connections(10)
@@ -84,24 +130,156 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         };
 
-    // Initialize Realmlist
-    if let Err(e) = init_realmlist(&mysql_auth_pool).await {
-        tracing::warn!("Failed to initialize realmlist: {}", e);
+    let mysql_world_pool = match MySqlPoolOptions::new()
+        .max_connections(5)
+        .connect(&mysql_world_url)
+        .await {
+            Ok(pool) => {
+                tracing::info!("Connected to MySQL World DB");
+                pool
+            },
+            Err(e) => {
+                tracing::error!("Failed to connect to MySQL World DB: {}", e);
+                return Err(e.into());
+            }
+        };
+
+    // Seed the realmlist from config; rows that already exist are left alone.
+    let realms_path = env::var("REALMS_CONFIG").unwrap_or_else(|_| "data/realms.json".to_string());
+    let realm_seeds = match realms::load_seeds(&realms_path) {
+        Ok(seeds) => seeds,
+        Err(e) => {
+            tracing::warn!("Failed to load realm config: {}", e);
+            Vec::new()
+        }
+    };
+    if let Err(e) = realms::seed_realmlist(&mysql_auth_pool, &realm_seeds).await {
+        tracing::warn!("Failed to seed realmlist: {}", e);
+    }
+
+    let realm_pools = realms::RealmPools::new(
+        realms::default_realm_id(),
+        mysql_char_pool.clone(),
+        format!("mysql://{}:{}@{}", mysql_user, mysql_pass, mysql_host),
+    );
+    for seed in &realm_seeds {
+        let Some(db) = seed.characters_db.as_ref().filter(|_| seed.id != realm_pools.default_id()) else {
+            continue;
+        };
+        match realm_pools.connect(seed.id, db) {
+            Ok(()) => tracing::info!("Using {} for realm {} characters", db, seed.id),
+            Err(e) => tracing::warn!("Invalid characters DB for realm {}: {}", seed.id, e),
+        }
+    }
+    if let Err(e) = realms::connect_saved(&mongo_db, &realm_pools).await {
+        tracing::warn!("Failed to load realm characters DBs: {}", e);
+    }
+
+    // Achievements come from the client tables; ACHIEVEMENTS_DATA is applied on
+    // top of them to flag raid bosses, and is the only source without DBC_DIR.
+    let dbc_dir = env::var("DBC_DIR").ok().map(std::path::PathBuf::from);
+    let mut achievement_catalog = match &dbc_dir {
+        Some(dir) => match achievements::AchievementCatalog::from_dbc(dir) {
+            Ok(catalog) => {
+                tracing::info!("Loaded {} achievements from {}", catalog.len(), dir.display());
+                catalog
+            },
+            Err(e) => {
+                tracing::warn!("Failed to load achievement tables: {}", e);
+                achievements::AchievementCatalog::default()
+            }
+        },
+        None => achievements::AchievementCatalog::default(),
+    };
+    let achievements_path = env::var("ACHIEVEMENTS_DATA").unwrap_or_else(|_| "data/achievements.json".to_string());
+    match achievements::AchievementCatalog::load(&achievements_path) {
+        Ok(overrides) => {
+            tracing::info!("Loaded {} achievement overrides from {}", overrides.len(), achievements_path);
+            achievement_catalog.merge(overrides);
+        },
+        Err(e) => tracing::warn!("Failed to load achievement data: {}", e),
+    }
+    if achievement_catalog.is_empty() {
+        tracing::warn!("Achievement catalog is empty, achievement points will be reported as 0");
+    }
+
+    // The client tables cover every class; TALENTS_DATA is a JSON override for
+    // setups without the extracted DBC files.
+    let talent_catalog = match (&dbc_dir, env::var("TALENTS_DATA")) {
+        (_, Ok(path)) => talents::TalentCatalog::load(&path).map(|c| (c, path)),
+        (Some(dir), Err(_)) => talents::TalentCatalog::from_dbc(dir).map(|c| (c, dir.display().to_string())),
+        (None, Err(_)) => Err("neither DBC_DIR nor TALENTS_DATA is set".to_string()),
+    };
+    let talent_catalog = match talent_catalog {
+        Ok((catalog, source)) => {
+            tracing::info!("Loaded talent trees for {} classes from {}", catalog.len(), source);
+            catalog
+        },
+        Err(e) => {
+            tracing::warn!("Failed to load talent data: {}", e);
+            talents::TalentCatalog::default()
+        }
+    };
+    if talent_catalog.is_empty() {
+        tracing::warn!("Talent catalog is empty, armory talent trees will be blank");
+    }
+
+    let faction_catalog = match &dbc_dir {
+        Some(dir) => reputation::FactionCatalog::from_dbc(dir).unwrap_or_else(|e| {
+            tracing::warn!("Failed to load faction table: {}", e);
+            reputation::FactionCatalog::default()
+        }),
+        None => reputation::FactionCatalog::default(),
+    };
+    if faction_catalog.is_empty() {
+        tracing::warn!("Faction catalog is empty, armory reputations will be blank");
+    } else {
+        tracing::info!("Loaded {} factions", faction_catalog.len());
+    }
+
+    let portrait_storage = portraits::storage_from_env()?;
+    let portrait_dir = env::var("PORTRAIT_DIR").unwrap_or_else(|_| "uploads/characters".to_string());
+
+    let soap_client = soap::SoapClient::from_env();
+    if !soap_client.is_configured() {
+        tracing::warn!("SOAP_USER is not set, worldserver commands are disabled");
     }
 
     let state = AppState {
         mongo: mongo_db,
         mysql_auth: mysql_auth_pool,
         mysql_char: mysql_char_pool,
+        realms: Arc::new(realm_pools),
+        mysql_world: mysql_world_pool,
+        achievements: Arc::new(achievement_catalog),
+        talents: Arc::new(talent_catalog),
+        factions: Arc::new(faction_catalog),
+        portraits: Arc::from(portrait_storage),
+        soap: Arc::new(soap_client),
+        status: Arc::new(status::StatusCache::default()),
     };
 
+    hall_of_fame::spawn_watcher(state.clone());
+    achievements::spawn_leaderboard(state.clone());
+    progress::spawn_snapshotter(state.clone());
+    auctions::spawn_price_sampler(state.clone());
+    gear::spawn_refresher(state.clone());
+    population::spawn_sampler(state.clone());
+    schedules::spawn_runner(state.clone());
+    tickets::spawn_notifier(state.clone());
+
     let cors = CorsLayer::new()
         .allow_origin(Any) 
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
         .allow_headers(Any);
 
     let app = Router::new()
-        .route("/health", get(health_check))
+        .route("/health", get(status::liveness))
+        .route("/health/live", get(status::liveness))
+        .route("/health/ready", get(status::readiness))
+        .route("/api/status", get(status::get_status))
+        .route("/api/status/history", get(population::get_status_history))
+        .route("/api/realms", get(realms::list_realms))
         .route("/api/auth/signup", post(handlers::signup))
         .route("/api/auth/login", post(handlers::login))
         .route("/api/auth/google", post(handlers::login_google))
@@ -109,8 +287,57 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/api/auth/me", get(handlers::me))
         .route("/api/auth/profile", put(handlers::update_profile))
         .route("/api/auth/check-username", post(handlers::check_username))
+        .route("/api/auth/link-account", post(handlers::link_game_account))
         .route("/api/characters", get(handlers::list_characters))
+        .route("/api/news", get(announcements::list_news))
+        .route("/api/game/reference", get(domain::get_reference))
+        .route("/api/characters/deleted", get(character_services::list_deleted_characters))
+        .route("/api/characters/deleted/:guid/restore", post(character_services::restore_deleted_character))
+        .route(
+            "/api/characters/:name/image",
+            post(portraits::upload_character_image).layer(DefaultBodyLimit::max(portraits::MAX_UPLOAD_BYTES + 64 * 1024)),
+        )
+        .nest_service("/api/uploads/characters", ServeDir::new(portrait_dir))
+        .route("/api/characters/:name/unstuck", post(character_services::unstuck_character))
+        .route("/api/characters/:name/transfer", post(character_services::transfer_character))
+        .route("/api/characters/:name/services", get(character_services::list_character_services))
+        .route("/api/characters/:name/services/:service", post(character_services::request_character_service))
+        .route("/api/points", get(points::get_my_points))
+        .route("/api/tickets", get(tickets::list_my_tickets))
+        .route("/api/arena/ladder", get(arena::get_ladder))
+        .route("/api/arena/teams/:id", get(arena::get_team))
+        .route("/api/armory/characters/:name", get(armory::get_character))
+        .route("/api/armory/characters/:name/history", get(progress::get_character_history))
+        .route("/api/armory/characters/:name/achievements", get(achievements::get_character_achievements))
+        .route("/api/ranking/top", get(ranking::get_top_characters))
+        .route("/api/ranking/achievements", get(achievements::get_achievement_leaderboard))
+        .route("/api/ranking/professions", get(professions::list_professions))
+        .route("/api/ranking/professions/:profession", get(professions::get_profession_ranking))
+        .route("/api/hall-of-fame", get(hall_of_fame::get_hall_of_fame))
+        .route("/api/auctions", get(auctions::search_auctions))
+        .route("/api/auctions/economy", get(auctions::get_economy_index))
+        .route("/api/auctions/items/:entry/history", get(auctions::get_item_price_history))
+        .route("/api/admin/realms", post(realms::create_realm))
+        .route("/api/admin/realms/:id", put(realms::update_realm).delete(realms::delete_realm))
+        .route("/api/admin/character-transfers", get(character_services::list_character_transfers))
+        .route("/api/admin/announcements", post(announcements::create_announcement))
+        .route("/api/admin/schedules", get(schedules::list_schedules).post(schedules::create_schedule))
+        .route("/api/admin/schedules/:id", delete(schedules::cancel_schedule))
+        .route("/api/admin/maintenance", get(maintenance::get_maintenance).put(maintenance::set_maintenance))
+        .route("/api/admin/tickets", get(tickets::list_tickets))
+        .route("/api/admin/tickets/:id", get(tickets::get_ticket))
+        .route("/api/admin/tickets/:id/assign", post(tickets::assign_ticket))
+        .route("/api/admin/tickets/:id/comment", post(tickets::comment_ticket))
+        .route("/api/admin/tickets/:id/answer", post(tickets::answer_ticket))
+        .route("/api/admin/tickets/:id/close", post(tickets::close_ticket))
+        .route("/api/admin/gm-actions", get(gm_actions::list_actions).post(gm_actions::run_action))
+        .route("/api/admin/gm-actions/audit", get(gm_actions::list_audit))
+        .route("/api/admin/users/role", put(gm_actions::set_user_role))
+        .route("/api/admin/users/points", post(points::grant_points))
+        .route("/api/admin/soap/command", post(soap::run_command))
+        .route("/api/admin/soap/server-info", get(soap::get_server_info))
         .route("/api/admin/config", get(handlers::get_server_config).put(handlers::update_server_config))
+        .route("/api/admin/config/sync", post(config_sync::sync_server_config))
         .layer(cors)
         .with_state(state);
 
@@ -124,34 +351,3 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
-
-async fn health_check() -> Json<serde_json::Value> {
-    Json(serde_json::json!({ "status": "ok", "backend": "rust" }))
-}
-
-async fn init_realmlist(pool: &sqlx::MySqlPool) -> Result<(), sqlx::Error> {
-    tracing::info!("Initializing Realmlist...");
-    
-    // Check if realmlist exists and update, or insert if missing
-    // We set address to game.aethelgard-wow.com and port 8085
-    let rows_affected = sqlx::query("UPDATE realmlist SET address = ?, name = ?, port = 8085 WHERE id = 1")
-        .bind("game.aethelgard-wow.com")
-        .bind("Aethelgard")
-        .execute(pool)
-        .await?
-        .rows_affected();
-
-    if rows_affected == 0 {
-        tracing::info!("Realmlist id 1 not found, inserting...");
-        // Default values for other columns: icon=1, color=0, timezone=1, allowedSecurityLevel=0, population=0
-        sqlx::query("INSERT INTO realmlist (id, name, address, port, icon, color, timezone, allowedSecurityLevel, population) VALUES (1, ?, ?, 8085, 1, 0, 1, 0, 0)")
-            .bind("Aethelgard")
-            .bind("game.aethelgard-wow.com")
-            .execute(pool)
-            .await?;
-    } else {
-        tracing::info!("Realmlist updated successfully.");
-    }
-    
-    Ok(())
-}
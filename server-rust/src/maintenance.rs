@@ -0,0 +1,309 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use mongodb::{
+    bson::doc,
+    options::UpdateOptions,
+    Collection,
+};
+use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder, Row};
+
+use crate::{
+    AppState,
+    clock::now_secs,
+    handlers::{authenticate, is_duplicate_key},
+    models::{MaintenanceMode, RealmMaintenanceBackup},
+};
+
+/// realmlist.flag bit that shows the realm as offline in the realm list.
+const REALM_FLAG_OFFLINE: u8 = 0x02;
+const DEFAULT_MESSAGE: &str = "The realm is under maintenance. Please try again later.";
+/// `_id` of the single maintenance document, so enabling is one conditional
+/// upsert instead of a read followed by a write.
+const MODE_ID: &str = "current";
+
+/// Account security level still allowed to log in during maintenance;
+/// 1 (moderators) and up by default.
+fn security_level() -> u8 {
+    std::env::var("MAINTENANCE_SECURITY_LEVEL")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1u8)
+        .clamp(1, 3)
+}
+
+fn collection(state: &AppState) -> Collection<MaintenanceMode> {
+    state.mongo.collection("maintenance_mode")
+}
+
+/// The active maintenance, if any. Lookup errors are logged and treated as
+/// no maintenance so a Mongo hiccup doesn't close signups.
+pub async fn current(state: &AppState) -> Option<MaintenanceMode> {
+    match collection(state).find_one(doc! { "enabled": true }, None).await {
+        Ok(mode) => mode,
+        Err(e) => {
+            tracing::warn!("Failed to read maintenance mode: {}", e);
+            None
+        }
+    }
+}
+
+/// 503 returned by account creation while maintenance is on.
+pub fn unavailable(mode: &MaintenanceMode) -> Response {
+    (StatusCode::SERVICE_UNAVAILABLE, mode.message.clone()).into_response()
+}
+
+/// Banner for the public config endpoint.
+pub fn banner(mode: &MaintenanceMode) -> serde_json::Value {
+    serde_json::json!({
+        "message": mode.message,
+        "since": mode.started_at,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceRequest {
+    pub enabled: bool,
+    pub message: Option<String>,
+    /// Realms to close; all realms when omitted.
+    pub realms: Option<Vec<u32>>,
+}
+
+impl MaintenanceRequest {
+    /// The trimmed message, None when blank.
+    fn validate(&self) -> Result<Option<&str>, &'static str> {
+        let message = self.message.as_deref().map(str::trim).filter(|m| !m.is_empty());
+        if message.is_some_and(|m| m.len() > 500) {
+            return Err("Message must be at most 500 characters");
+        }
+        if self.realms.as_ref().is_some_and(|r| r.is_empty()) {
+            return Err("Realms must not be empty; omit it to close all realms");
+        }
+        Ok(message)
+    }
+}
+
+/// Raises the security level and sets the offline flag on the given realms
+/// (all when None), returning their previous values.
+async fn close_realms(state: &AppState, realms: Option<&[u32]>) -> Result<Vec<RealmMaintenanceBackup>, sqlx::Error> {
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new("SELECT id, allowedSecurityLevel, flag FROM realmlist");
+    if let Some(ids) = realms {
+        builder.push(" WHERE id IN (");
+        let mut separated = builder.separated(", ");
+        for id in ids {
+            separated.push_bind(*id);
+        }
+        builder.push(")");
+    }
+
+    let mut tx = state.mysql_auth.begin().await?;
+    let backups: Vec<RealmMaintenanceBackup> = builder.build().fetch_all(&mut *tx).await?.iter().map(|row| RealmMaintenanceBackup {
+        realm: row.try_get::<u32, _>("id").unwrap_or_default(),
+        allowed_security_level: row.try_get::<u8, _>("allowedSecurityLevel").unwrap_or_default(),
+        flag: row.try_get::<u8, _>("flag").unwrap_or_default(),
+    }).collect();
+
+    for backup in &backups {
+        sqlx::query("UPDATE realmlist SET allowedSecurityLevel = ?, flag = flag | ? WHERE id = ?")
+            .bind(backup.allowed_security_level.max(security_level()))
+            .bind(REALM_FLAG_OFFLINE)
+            .bind(backup.realm)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(backups)
+}
+
+async fn reopen_realms(state: &AppState, backups: &[RealmMaintenanceBackup]) -> Result<(), sqlx::Error> {
+    let mut tx = state.mysql_auth.begin().await?;
+    for backup in backups {
+        sqlx::query("UPDATE realmlist SET allowedSecurityLevel = ?, flag = ? WHERE id = ?")
+            .bind(backup.allowed_security_level)
+            .bind(backup.flag)
+            .bind(backup.realm)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await
+}
+
+pub async fn get_maintenance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    match current(&state).await {
+        Some(mode) => Json(mode).into_response(),
+        None => Json(serde_json::json!({ "enabled": false })).into_response(),
+    }
+}
+
+/// Turns maintenance on or off. The maintenance document is claimed before
+/// realmlist is touched, so turning it on again, even concurrently, only
+/// updates the message and the saved realmlist values are never overwritten
+/// by maintenance ones. Realm edits made while maintenance is on are reverted
+/// when it is lifted.
+pub async fn set_maintenance(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<MaintenanceRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let message = match payload.validate() {
+        Ok(m) => m,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    if !payload.enabled {
+        let mode = match collection(&state).find_one_and_delete(doc! { "enabled": true }, None).await {
+            Ok(Some(mode)) => mode,
+            Ok(None) => return Json(serde_json::json!({ "enabled": false })).into_response(),
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+        };
+        if let Err(e) = reopen_realms(&state, &mode.realms).await {
+            tracing::error!("Failed to restore realmlist after maintenance: {}", e);
+            // Put the backup back so lifting can be retried.
+            if let Err(e) = store(&state, &mode).await {
+                tracing::error!("Failed to keep maintenance backup after restore error: {}", e);
+            }
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+        tracing::info!("Maintenance lifted by {}", claims.sub);
+        return Json(serde_json::json!({ "enabled": false })).into_response();
+    }
+
+    let mut mode = MaintenanceMode {
+        enabled: true,
+        message: message.unwrap_or(DEFAULT_MESSAGE).to_string(),
+        started_at: now_secs(),
+        started_by: claims.sub.clone(),
+        realms: Vec::new(),
+    };
+    // Only one enable call can match a missing or disabled document; the
+    // others hit the duplicate `_id` and just update the message.
+    let claimed = collection(&state).update_one(
+        doc! { "_id": MODE_ID, "enabled": { "$ne": true } },
+        doc! { "$set": {
+            "enabled": true,
+            "message": &mode.message,
+            "startedAt": mode.started_at,
+            "startedBy": &mode.started_by,
+            "realms": [],
+        } },
+        UpdateOptions::builder().upsert(true).build(),
+    ).await;
+    match claimed {
+        Ok(_) => {},
+        Err(e) if is_duplicate_key(&e) => {
+            let Some(mut active) = current(&state).await else {
+                return (StatusCode::CONFLICT, "Maintenance is changing, try again").into_response();
+            };
+            if let Some(message) = message {
+                active.message = message.to_string();
+                if collection(&state).update_one(doc! { "_id": MODE_ID }, doc! { "$set": { "message": &active.message } }, None).await.is_err() {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+                }
+            }
+            return Json(active).into_response();
+        },
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+
+    mode.realms = match close_realms(&state, payload.realms.as_deref()).await {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!("Failed to close realms for maintenance: {}", e);
+            let _ = collection(&state).delete_one(doc! { "_id": MODE_ID }, None).await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let saved = match mongodb::bson::to_bson(&mode.realms) {
+        Ok(backups) => collection(&state).update_one(
+            doc! { "_id": MODE_ID, "enabled": true },
+            doc! { "$set": { "realms": backups } },
+            None,
+        ).await.is_ok_and(|r| r.matched_count == 1),
+        Err(_) => false,
+    };
+    if !saved {
+        // Without the saved values, or if maintenance was lifted meanwhile,
+        // the realms would never be reopened.
+        if let Err(e) = reopen_realms(&state, &mode.realms).await {
+            tracing::error!("Failed to roll back realmlist after maintenance error: {}", e);
+        }
+        let _ = collection(&state).delete_one(doc! { "_id": MODE_ID }, None).await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+
+    tracing::info!("Maintenance started by {} on {} realms", mode.started_by, mode.realms.len());
+    Json(mode).into_response()
+}
+
+async fn store(state: &AppState, mode: &MaintenanceMode) -> Result<(), mongodb::error::Error> {
+    let document = mongodb::bson::to_document(mode)?;
+    collection(state).update_one(
+        doc! { "_id": MODE_ID },
+        doc! { "$set": document },
+        UpdateOptions::builder().upsert(true).build(),
+    ).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(message: Option<&str>, realms: Option<Vec<u32>>) -> MaintenanceRequest {
+        MaintenanceRequest { enabled: true, message: message.map(str::to_string), realms }
+    }
+
+    #[test]
+    fn validate_trims_the_message_and_treats_blank_as_default() {
+        assert_eq!(request(Some("  Patching  "), None).validate(), Ok(Some("Patching")));
+        assert_eq!(request(Some("   "), None).validate(), Ok(None));
+        assert_eq!(request(None, Some(vec![1])).validate(), Ok(None));
+    }
+
+    #[test]
+    fn validate_rejects_long_messages_and_empty_realm_lists() {
+        assert!(request(Some(&"x".repeat(501)), None).validate().is_err());
+        assert!(request(Some(&"x".repeat(500)), None).validate().is_ok());
+        assert!(request(None, Some(Vec::new())).validate().is_err());
+    }
+
+    #[test]
+    fn banner_leaves_out_the_realm_backups() {
+        let mode = MaintenanceMode {
+            enabled: true,
+            message: DEFAULT_MESSAGE.to_string(),
+            started_at: 1_700_000_000,
+            started_by: "admin".to_string(),
+            realms: vec![RealmMaintenanceBackup { realm: 1, allowed_security_level: 0, flag: 0 }],
+        };
+
+        assert_eq!(banner(&mode), serde_json::json!({
+            "message": DEFAULT_MESSAGE,
+            "since": 1_700_000_000,
+        }));
+    }
+}
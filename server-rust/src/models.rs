@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::domain::{CharacterTraits, Money};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct User {
@@ -15,9 +18,11 @@ pub struct User {
     #[serde(rename = "avatarUrl", skip_serializing_if = "Option::is_none")]
     pub avatar_url: Option<String>,
     #[serde(default)]
-    pub role: String, // "user", "admin"
+    pub role: String, // "user", "moderator", "gamemaster", "admin"
     #[serde(rename = "gameId", skip_serializing_if = "Option::is_none")]
     pub game_id: Option<u32>,
+    #[serde(rename = "linkedAccounts", default)]
+    pub linked_accounts: Vec<u32>,
     #[serde(default)]
     pub created_at: i64,
 }
@@ -47,6 +52,12 @@ pub struct LoginGameRequest {
     pub password: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkAccountRequest {
+    pub username: String,
+    pub password: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GoogleLoginRequest {
     pub token: String,
@@ -94,3 +105,700 @@ pub struct ServerConfig {
     pub rep_rate: f64,
     pub motd: Option<String>,
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterSummary {
+    pub name: String,
+    #[serde(flatten)]
+    pub traits: CharacterTraits,
+    pub level: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeletedCharacter {
+    pub guid: u32,
+    pub account: Option<u32>,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub traits: CharacterTraits,
+    pub level: u8,
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfessionRankingEntry {
+    pub rank: usize,
+    pub name: String,
+    #[serde(flatten)]
+    pub traits: CharacterTraits,
+    pub level: u8,
+    pub value: u16,
+    pub max: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArenaTeamMember {
+    pub guid: u32,
+    pub name: String,
+    #[serde(flatten)]
+    pub traits: CharacterTraits,
+    pub level: u8,
+    #[serde(rename = "personalRating")]
+    pub personal_rating: u16,
+    #[serde(rename = "seasonGames")]
+    pub season_games: u16,
+    #[serde(rename = "seasonWins")]
+    pub season_wins: u16,
+    #[serde(rename = "weekGames")]
+    pub week_games: u16,
+    #[serde(rename = "weekWins")]
+    pub week_wins: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArenaTeam {
+    pub id: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub bracket: u8,
+    pub rating: u16,
+    pub rank: u32,
+    #[serde(rename = "seasonGames")]
+    pub season_games: u16,
+    #[serde(rename = "seasonWins")]
+    pub season_wins: u16,
+    #[serde(rename = "weekGames")]
+    pub week_games: u16,
+    #[serde(rename = "weekWins")]
+    pub week_wins: u16,
+    pub captain: Option<String>,
+    pub faction: Option<String>,
+    #[serde(rename = "factionName")]
+    pub faction_name: Option<String>,
+    pub members: Vec<ArenaTeamMember>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArmoryCharacter {
+    pub guid: u32,
+    pub name: String,
+    #[serde(flatten)]
+    pub traits: CharacterTraits,
+    pub level: u8,
+    pub xp: u32,
+    pub money: u32,
+    #[serde(rename = "totalTime")]
+    pub total_time: u32,
+    pub online: bool,
+    pub guild: Option<String>,
+    #[serde(rename = "achievementPoints")]
+    pub achievement_points: u32,
+    pub professions: Vec<CharacterProfession>,
+    pub reputations: Vec<CharacterReputation>,
+    pub quests: CharacterQuestLog,
+    pub talents: Vec<CharacterTalentSpec>,
+    #[serde(rename = "averageItemLevel")]
+    pub average_item_level: Option<f64>,
+    #[serde(rename = "gearScore")]
+    pub gear_score: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterProfession {
+    pub id: u16,
+    pub slug: String,
+    pub name: String,
+    pub value: u16,
+    pub max: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterAchievement {
+    pub id: u32,
+    pub name: Option<String>,
+    pub points: u32,
+    pub category: String,
+    pub date: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AchievementProgress {
+    pub criteria: u32,
+    pub achievement: Option<u32>,
+    pub counter: u32,
+    pub date: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AchievementCategorySummary {
+    pub category: String,
+    pub completed: u32,
+    pub points: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterAchievements {
+    pub character: String,
+    #[serde(rename = "totalPoints")]
+    pub total_points: u32,
+    pub categories: Vec<AchievementCategorySummary>,
+    pub completed: Vec<CharacterAchievement>,
+    pub progress: Vec<AchievementProgress>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AchievementLeaderboardEntry {
+    pub name: String,
+    #[serde(flatten)]
+    pub traits: CharacterTraits,
+    pub level: u8,
+    pub points: u32,
+    pub completed: u32,
+}
+
+/// Achievement points ranking of one realm, refreshed in the background.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementLeaderboard {
+    #[serde(rename = "_id")]
+    pub realm: u32,
+    pub entries: Vec<AchievementScore>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AchievementScore {
+    pub guid: u32,
+    pub points: u32,
+    pub completed: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallOfFameCharacter {
+    pub name: String,
+    #[serde(flatten)]
+    pub traits: CharacterTraits,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HallOfFameEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub realm: u32,
+    #[serde(rename = "achievementId")]
+    pub achievement_id: u32,
+    pub title: String,
+    pub kind: String, // "realm_first", "first_kill"
+    pub date: i64,
+    pub characters: Vec<HallOfFameCharacter>,
+    #[serde(rename = "recordedAt")]
+    pub recorded_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnstuckLog {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub realm: u32,
+    pub guid: u32,
+    pub name: String,
+    #[serde(rename = "requestedBy")]
+    pub requested_by: String,
+    pub at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterServiceLog {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub realm: u32,
+    pub guid: u32,
+    pub name: String,
+    pub service: String,
+    #[serde(rename = "requestedBy")]
+    pub requested_by: String,
+    pub cost: i64,
+    pub at: i64,
+}
+
+/// A change to a user's points made by an admin.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PointsTransaction {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub amount: i64,
+    /// Balance after the change.
+    pub balance: i64,
+    pub reason: String,
+    #[serde(rename = "grantedBy")]
+    pub granted_by: String,
+    pub at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterTransferLog {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub realm: u32,
+    pub guid: u32,
+    pub name: String,
+    #[serde(rename = "fromAccount")]
+    pub from_account: u32,
+    #[serde(rename = "toAccount")]
+    pub to_account: u32,
+    #[serde(rename = "requestedBy")]
+    pub requested_by: String,
+    pub at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterPortrait {
+    pub realm: u32,
+    pub guid: u32,
+    pub name: String,
+    pub urls: HashMap<String, String>,
+    /// Hash of the uploaded image, part of every file name so a new upload
+    /// never reuses a cached URL.
+    pub version: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RankingEntry {
+    pub name: String,
+    #[serde(flatten)]
+    pub traits: CharacterTraits,
+    pub level: u8,
+    #[serde(rename = "totalTime")]
+    pub total_time: u32,
+    #[serde(rename = "guildName")]
+    pub guild_name: Option<String>,
+    #[serde(rename = "imageUrl", skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    #[serde(rename = "averageItemLevel", skip_serializing_if = "Option::is_none")]
+    pub average_item_level: Option<f64>,
+    #[serde(rename = "gearScore", skip_serializing_if = "Option::is_none")]
+    pub gear_score: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterSnapshot {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub realm: u32,
+    pub guid: u32,
+    pub name: String,
+    pub at: i64,
+    pub level: u8,
+    pub xp: u32,
+    pub money: u32,
+    #[serde(rename = "totalTime")]
+    pub total_time: u32,
+    #[serde(rename = "achievementPoints")]
+    pub achievement_points: u32,
+    #[serde(rename = "expiresAt", default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuctionItem {
+    pub entry: u32,
+    pub name: String,
+    pub quality: u8,
+    #[serde(rename = "qualityName")]
+    pub quality_name: String,
+    #[serde(rename = "itemLevel")]
+    pub item_level: u16,
+    #[serde(rename = "requiredLevel")]
+    pub required_level: u8,
+    pub count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuctionListing {
+    pub id: u32,
+    pub house: String,
+    pub item: AuctionItem,
+    pub seller: Option<String>,
+    #[serde(rename = "startBid")]
+    pub start_bid: Money,
+    #[serde(rename = "currentBid", skip_serializing_if = "Option::is_none")]
+    pub current_bid: Option<Money>,
+    pub buyout: Option<Money>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuctionPriceSample {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    /// Realm the auction house belongs to.
+    pub realm: u32,
+    #[serde(rename = "itemEntry")]
+    pub item_entry: u32,
+    pub at: i64,
+    /// Lowest buyout per unit, in copper.
+    pub min: i64,
+    /// Median buyout per unit, in copper.
+    pub median: i64,
+    pub listings: u32,
+    pub quantity: u32,
+    #[serde(rename = "expiresAt", default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EconomySample {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub realm: u32,
+    pub at: i64,
+    /// Copper held by all player characters.
+    #[serde(rename = "moneySupply")]
+    pub money_supply: i64,
+    pub listings: u32,
+    #[serde(rename = "itemsSampled")]
+    pub items_sampled: u32,
+    #[serde(rename = "expiresAt", default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterReputation {
+    pub id: u16,
+    pub name: String,
+    pub standing: i32,
+    pub rank: String,
+    #[serde(rename = "rankName")]
+    pub rank_name: String,
+    /// Progress into the current rank and the rank's size.
+    pub value: i32,
+    pub max: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterQuest {
+    pub id: u32,
+    pub title: Option<String>,
+    pub level: Option<i16>,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterQuestLog {
+    pub completed: u32,
+    #[serde(rename = "completedQuests")]
+    pub completed_quests: Vec<CharacterQuest>,
+    pub active: Vec<CharacterQuest>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TalentTreeSummary {
+    pub name: String,
+    pub points: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterGlyph {
+    pub slot: u8,
+    pub id: u32,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterTalentSpec {
+    pub group: u8,
+    pub active: bool,
+    pub trees: Vec<TalentTreeSummary>,
+    /// Per-tree rank digits joined with `-`, as used by talent calculators.
+    /// Omitted when the class has no talent layout.
+    #[serde(rename = "exportString", skip_serializing_if = "Option::is_none")]
+    pub export_string: Option<String>,
+    pub glyphs: Vec<CharacterGlyph>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterGear {
+    pub realm: u32,
+    pub guid: u32,
+    #[serde(rename = "averageItemLevel")]
+    pub average_item_level: f64,
+    #[serde(rename = "gearScore")]
+    pub gear_score: u32,
+    #[serde(rename = "equippedItems")]
+    pub equipped_items: u32,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceProbe {
+    pub online: bool,
+    #[serde(rename = "latencyMs", skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealmUptime {
+    #[serde(rename = "startedAt")]
+    pub started_at: i64,
+    /// Seconds the worldserver has been up, as last reported by it.
+    pub uptime: u32,
+    #[serde(rename = "maxPlayers")]
+    pub max_players: u16,
+    pub revision: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealmStatus {
+    /// online, degraded or offline.
+    pub status: String,
+    pub authserver: ServiceProbe,
+    pub worldserver: ServiceProbe,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime: Option<RealmUptime>,
+    /// Players online across all realms.
+    #[serde(rename = "onlinePlayers")]
+    pub online_players: Option<u32>,
+    /// Players online per realm id, for realms whose count could be read.
+    #[serde(rename = "realmPlayers", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub realm_players: BTreeMap<u32, u32>,
+    pub services: HashMap<String, ServiceProbe>,
+    #[serde(rename = "checkedAt")]
+    pub checked_at: i64,
+    /// Upcoming restarts and shutdowns planned by the admins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintenance: Vec<MaintenanceWindow>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Realm {
+    pub id: u32,
+    pub name: String,
+    pub address: String,
+    pub port: u16,
+    pub icon: u8,
+    pub flag: u8,
+    pub timezone: u8,
+    #[serde(rename = "allowedSecurityLevel")]
+    pub allowed_security_level: u8,
+    pub population: f32,
+    /// Whether a characters database is configured for this realm.
+    #[serde(rename = "charactersAvailable", default)]
+    pub characters_available: bool,
+}
+
+/// Characters database of a realm, set through the admin realm endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealmDatabase {
+    #[serde(rename = "_id")]
+    pub realm: u32,
+    #[serde(rename = "charactersDb")]
+    pub characters_db: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PopulationSample {
+    pub realm: u32,
+    pub at: i64,
+    pub online: u32,
+    pub alliance: u32,
+    pub horde: u32,
+    #[serde(rename = "worldserverUp")]
+    pub worldserver_up: bool,
+    #[serde(rename = "expiresAt", default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PopulationPeak {
+    pub online: u32,
+    pub alliance: u32,
+    pub horde: u32,
+    pub at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DailyPopulation {
+    /// UTC day, as YYYY-MM-DD.
+    pub date: String,
+    pub samples: u32,
+    #[serde(rename = "averageOnline")]
+    pub average_online: f64,
+    #[serde(rename = "averageAlliance")]
+    pub average_alliance: f64,
+    #[serde(rename = "averageHorde")]
+    pub average_horde: f64,
+    #[serde(rename = "peakOnline")]
+    pub peak_online: u32,
+    #[serde(rename = "uptimePercent")]
+    pub uptime_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PopulationHistory {
+    pub realm: u32,
+    pub days: i64,
+    pub from: i64,
+    pub to: i64,
+    pub samples: u32,
+    pub peak: Option<PopulationPeak>,
+    #[serde(rename = "averageOnline")]
+    pub average_online: Option<f64>,
+    #[serde(rename = "uptimePercent")]
+    pub uptime_percent: f64,
+    pub daily: Vec<DailyPopulation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewsPost {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub title: String,
+    pub content: String,
+    pub author: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnouncementLog {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub message: String,
+    /// announce (chat), notify (on-screen) or both.
+    pub kind: String,
+    #[serde(rename = "newsId", skip_serializing_if = "Option::is_none")]
+    pub news_id: Option<String>,
+    #[serde(rename = "requestedBy")]
+    pub requested_by: String,
+    pub at: i64,
+    /// Command that failed after the ones before it went out, e.g. the
+    /// `notify` half of a `both` broadcast.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub failed: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerSchedule {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    /// restart or shutdown.
+    pub action: String,
+    /// When the worldserver goes down.
+    #[serde(rename = "scheduledAt")]
+    pub scheduled_at: i64,
+    /// Expected downtime, shown as the end of the maintenance window.
+    #[serde(rename = "durationMinutes", skip_serializing_if = "Option::is_none")]
+    pub duration_minutes: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// pending, handing_off, started, completed, cancelled or failed.
+    pub status: String,
+    /// Countdown offsets (seconds before `scheduledAt`) already announced.
+    #[serde(default)]
+    pub announced: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(rename = "createdBy")]
+    pub created_by: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub action: String,
+    #[serde(rename = "startsAt")]
+    pub starts_at: i64,
+    #[serde(rename = "endsAt", skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// realmlist values changed by maintenance mode, restored when it is lifted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealmMaintenanceBackup {
+    pub realm: u32,
+    #[serde(rename = "allowedSecurityLevel")]
+    pub allowed_security_level: u8,
+    pub flag: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceMode {
+    pub enabled: bool,
+    pub message: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: i64,
+    #[serde(rename = "startedBy")]
+    pub started_by: String,
+    #[serde(default)]
+    pub realms: Vec<RealmMaintenanceBackup>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GmTicket {
+    pub id: u32,
+    pub realm: u32,
+    pub character: String,
+    #[serde(rename = "playerGuid")]
+    pub player_guid: u32,
+    /// open, assigned, answered, closed or deleted.
+    pub status: String,
+    pub description: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    /// Staff-only fields, left out of the player view.
+    #[serde(rename = "assignedTo", skip_serializing_if = "Option::is_none")]
+    pub assigned_to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub escalated: Option<bool>,
+    #[serde(rename = "mapId", skip_serializing_if = "Option::is_none")]
+    pub map_id: Option<u16>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GmAuditEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub action: String,
+    pub params: mongodb::bson::Bson,
+    /// Console command sent to the worldserver, when it got that far.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// success, failed, denied, invalid or confirmation-required.
+    pub outcome: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(rename = "requestedBy")]
+    pub requested_by: String,
+    pub role: String,
+    pub at: i64,
+}
+
+/// Pending confirmation of a destructive GM action, redeemed once.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GmConfirmation {
+    pub token: String,
+    pub action: String,
+    pub command: String,
+    #[serde(rename = "requestedBy")]
+    pub requested_by: String,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: i64,
+}
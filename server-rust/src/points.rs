@@ -0,0 +1,149 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    clock::now_secs,
+    handlers::authenticate,
+    models::{PointsTransaction, User},
+};
+
+/// Largest grant or deduction an admin can make at once.
+const MAX_GRANT: i64 = 1_000_000;
+
+fn balances(state: &AppState) -> Collection<Document> {
+    state.mongo.collection("user_points")
+}
+
+/// One balance per user, so concurrent first grants can't upsert two.
+pub async fn ensure_indexes(mongo: &mongodb::Database) -> Result<(), mongodb::error::Error> {
+    let index = IndexModel::builder()
+        .keys(doc! { "userId": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    mongo.collection::<Document>("user_points").create_index(index, None).await?;
+    Ok(())
+}
+
+async fn balance(state: &AppState, user: &str) -> Result<i64, mongodb::error::Error> {
+    let document = balances(state).find_one(doc! { "userId": user }, None).await?;
+    Ok(document.and_then(|d| d.get_i64("balance").ok()).unwrap_or(0))
+}
+
+/// Takes `cost` points from the user. The decrement is conditional so two
+/// concurrent requests can't overdraw the balance; false when it is too low.
+pub(crate) async fn charge(state: &AppState, user: &str, cost: i64) -> Result<bool, mongodb::error::Error> {
+    let result = balances(state).update_one(
+        doc! { "userId": user, "balance": { "$gte": cost } },
+        doc! { "$inc": { "balance": -cost } },
+        None,
+    ).await?;
+    Ok(result.modified_count > 0)
+}
+
+pub(crate) async fn refund(state: &AppState, user: &str, cost: i64) {
+    if let Err(e) = balances(state).update_one(doc! { "userId": user }, doc! { "$inc": { "balance": cost } }, None).await {
+        tracing::error!("Failed to refund {} points to {}: {}", cost, user, e);
+    }
+}
+
+pub async fn get_my_points(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    match balance(&state, &claims.sub).await {
+        Ok(balance) => Json(serde_json::json!({ "balance": balance })).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GrantPointsRequest {
+    pub email: String,
+    /// Points to add; negative to deduct.
+    pub amount: i64,
+    pub reason: String,
+}
+
+/// Credits or debits a user's points. Deductions never take the balance
+/// below zero. Every change is kept in `points_ledger`.
+pub async fn grant_points(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<GrantPointsRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+    if payload.amount == 0 || payload.amount.abs() > MAX_GRANT {
+        return (StatusCode::BAD_REQUEST, "Amount must be between -1000000 and 1000000 and not 0").into_response();
+    }
+    let reason = payload.reason.trim();
+    if reason.is_empty() || reason.len() > 200 {
+        return (StatusCode::BAD_REQUEST, "Reason must be 1-200 characters").into_response();
+    }
+
+    let users: Collection<User> = state.mongo.collection("users");
+    let user_id = match users.find_one(doc! { "email": &payload.email }, None).await {
+        Ok(Some(User { id: Some(id), .. })) => id.to_hex(),
+        Ok(_) => return (StatusCode::NOT_FOUND, "User not found").into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    // Credits create the balance on first use; debits need enough points.
+    let (filter, upsert) = if payload.amount > 0 {
+        (doc! { "userId": &user_id }, true)
+    } else {
+        (doc! { "userId": &user_id, "balance": { "$gte": -payload.amount } }, false)
+    };
+    let updated = balances(&state).find_one_and_update(
+        filter,
+        doc! { "$inc": { "balance": payload.amount } },
+        FindOneAndUpdateOptions::builder().upsert(upsert).return_document(ReturnDocument::After).build(),
+    ).await;
+    let balance = match updated {
+        Ok(Some(document)) => document.get_i64("balance").unwrap_or(0),
+        Ok(None) => return (StatusCode::CONFLICT, "Balance is lower than the deduction").into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    let entry = PointsTransaction {
+        id: None,
+        user_id: user_id.clone(),
+        amount: payload.amount,
+        balance,
+        reason: reason.to_string(),
+        granted_by: claims.sub.clone(),
+        at: now_secs(),
+    };
+    let ledger: Collection<PointsTransaction> = state.mongo.collection("points_ledger");
+    if let Err(e) = ledger.insert_one(entry, None).await {
+        tracing::error!("Failed to record points grant for {}: {}", user_id, e);
+    }
+    tracing::info!("Admin {} changed points of {} by {}: {}", claims.sub, payload.email, payload.amount, reason);
+
+    Json(serde_json::json!({
+        "email": payload.email,
+        "amount": payload.amount,
+        "balance": balance,
+    })).into_response()
+}
@@ -0,0 +1,272 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::doc,
+    options::FindOptions,
+    Collection, IndexModel,
+};
+use serde::Deserialize;
+use sqlx::Row;
+use std::collections::BTreeMap;
+
+use crate::{
+    AppState,
+    clock::{interval_from_env, now_secs},
+    domain::{Faction, Race},
+    models::{DailyPopulation, PopulationHistory, PopulationPeak, PopulationSample},
+    realms::RealmDb,
+    retention,
+};
+
+const DAY_SECS: i64 = 24 * 3600;
+
+/// How long after its last `uptime` update a worldserver still counts as up.
+/// AzerothCore refreshes the row every `UpdateUptimeInterval` (10 minutes by
+/// default).
+fn uptime_stale_secs() -> i64 {
+    std::env::var("UPTIME_STALE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(900)
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub days: Option<i64>,
+}
+
+/// Starts the background task that records online players per realm and
+/// faction. Samples are kept for `POPULATION_RETENTION_DAYS` (120 by default,
+/// past the longest history served). `POPULATION_SAMPLE_INTERVAL_SECS=0`
+/// disables it.
+pub fn spawn_sampler(state: AppState) {
+    let Some(period) = interval_from_env("POPULATION_SAMPLE_INTERVAL_SECS", 300) else {
+        tracing::info!("Population sampling disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let collection: Collection<PopulationSample> = state.mongo.collection("population_samples");
+        let index = IndexModel::builder().keys(doc! { "realm": 1, "at": 1 }).build();
+        if let Err(e) = collection.create_index(index, None).await {
+            tracing::warn!("Failed to create population_samples index: {}", e);
+        }
+        let retention_days = retention::retention_days("POPULATION_RETENTION_DAYS", 120);
+        if let Err(e) = retention::ensure_ttl(&collection, retention_days).await {
+            tracing::warn!("Failed to set up population_samples retention: {}", e);
+        }
+
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            for (realm, pool) in state.realms.all() {
+                match sample_realm(&state, realm, &pool, retention_days).await {
+                    Ok(sample) => {
+                        if let Err(e) = collection.insert_one(sample, None).await {
+                            tracing::warn!("Failed to store population sample for realm {}: {}", realm, e);
+                        }
+                    },
+                    Err(e) => tracing::warn!("Population sampling failed for realm {}: {}", realm, e),
+                }
+            }
+        }
+    });
+}
+
+/// Whether the realm's worldserver has reported its uptime recently.
+pub(crate) async fn worldserver_up(state: &AppState, realm: u32, now: i64) -> Result<bool, sqlx::Error> {
+    let last_seen: Option<i64> = sqlx::query_scalar(
+        "SELECT CAST(starttime + uptime AS SIGNED) FROM uptime WHERE realmid = ? ORDER BY starttime DESC LIMIT 1",
+    )
+        .bind(realm)
+        .fetch_optional(&state.mysql_auth)
+        .await?;
+    Ok(last_seen.is_some_and(|seen| now - seen <= uptime_stale_secs()))
+}
+
+async fn sample_realm(state: &AppState, realm: u32, pool: &sqlx::MySqlPool, retention_days: i64) -> Result<PopulationSample, sqlx::Error> {
+    let now = now_secs();
+    let up = worldserver_up(state, realm, now).await?;
+
+    let mut sample = PopulationSample {
+        realm,
+        at: now,
+        online: 0,
+        alliance: 0,
+        horde: 0,
+        worldserver_up: up,
+        expires_at: retention::expires_at(now, retention_days),
+    };
+    // `online = 1` survives a worldserver crash, so counts are only taken
+    // while it is reporting uptime.
+    if !up {
+        return Ok(sample);
+    }
+
+    let rows = sqlx::query("SELECT race, COUNT(*) AS players FROM characters WHERE online = 1 GROUP BY race")
+        .fetch_all(pool)
+        .await?;
+    for row in rows {
+        let players = row.try_get::<i64, _>("players").unwrap_or_default() as u32;
+        sample.online += players;
+        match row.try_get::<u8, _>("race").ok().and_then(Race::from_id).map(Race::faction) {
+            Some(Faction::Alliance) => sample.alliance += players,
+            Some(Faction::Horde) => sample.horde += players,
+            None => {},
+        }
+    }
+    Ok(sample)
+}
+
+/// Worldserver runs from the `uptime` table overlapping [from, to], merged
+/// into disjoint (start, end) intervals.
+async fn uptime_intervals(state: &AppState, realm: u32, from: i64, to: i64) -> Result<Vec<(i64, i64)>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT starttime, uptime FROM uptime \
+         WHERE realmid = ? AND starttime + uptime >= ? AND starttime <= ? \
+         ORDER BY starttime",
+    )
+        .bind(realm)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&state.mysql_auth)
+        .await?;
+
+    let mut merged: Vec<(i64, i64)> = Vec::new();
+    for row in rows {
+        let start = row.try_get::<u32, _>("starttime").unwrap_or_default() as i64;
+        let end = start + row.try_get::<u32, _>("uptime").unwrap_or_default() as i64;
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    Ok(merged)
+}
+
+/// Share of [from, to) covered by the intervals, as a percentage.
+fn uptime_percent(intervals: &[(i64, i64)], from: i64, to: i64) -> f64 {
+    if to <= from {
+        return 0.0;
+    }
+    let covered: i64 = intervals.iter()
+        .map(|(start, end)| ((*end).min(to) - (*start).max(from)).max(0))
+        .sum();
+    (covered as f64 / (to - from) as f64 * 10000.0).round() / 100.0
+}
+
+fn day_label(day_start: i64) -> String {
+    chrono::DateTime::from_timestamp(day_start, 0)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Peak and average concurrency plus uptime for the last `days` days, overall
+/// and per UTC day.
+pub async fn get_status_history(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    Query(params): Query<HistoryQuery>,
+) -> impl IntoResponse {
+    let days = params.days.unwrap_or(7).clamp(1, 90);
+    let to = now_secs();
+    let from = to - days * DAY_SECS;
+
+    let collection: Collection<PopulationSample> = state.mongo.collection("population_samples");
+    let cursor = match collection.find(
+        doc! { "realm": realm.id, "at": { "$gte": from } },
+        FindOptions::builder().sort(doc! { "at": 1 }).build(),
+    ).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+    let samples: Vec<PopulationSample> = match cursor.try_collect().await {
+        Ok(s) => s,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    let intervals = match uptime_intervals(&state, realm.id, from, to).await {
+        Ok(i) => i,
+        Err(e) => {
+            tracing::error!("Failed to load uptime for realm {}: {}", realm.id, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let mut by_day: BTreeMap<i64, Vec<&PopulationSample>> = BTreeMap::new();
+    let mut day_start = from - from.rem_euclid(DAY_SECS);
+    while day_start < to {
+        by_day.insert(day_start, Vec::new());
+        day_start += DAY_SECS;
+    }
+    for sample in &samples {
+        by_day.entry(sample.at - sample.at.rem_euclid(DAY_SECS)).or_default().push(sample);
+    }
+
+    let daily: Vec<DailyPopulation> = by_day.into_iter().map(|(day_start, day)| {
+        let count = day.len().max(1) as f64;
+        DailyPopulation {
+            date: day_label(day_start),
+            samples: day.len() as u32,
+            average_online: round1(day.iter().map(|s| s.online as f64).sum::<f64>() / count),
+            average_alliance: round1(day.iter().map(|s| s.alliance as f64).sum::<f64>() / count),
+            average_horde: round1(day.iter().map(|s| s.horde as f64).sum::<f64>() / count),
+            peak_online: day.iter().map(|s| s.online).max().unwrap_or(0),
+            uptime_percent: uptime_percent(&intervals, day_start.max(from), (day_start + DAY_SECS).min(to)),
+        }
+    }).collect();
+
+    let peak = samples.iter().max_by_key(|s| (s.online, -s.at)).map(|s| PopulationPeak {
+        online: s.online,
+        alliance: s.alliance,
+        horde: s.horde,
+        at: s.at,
+    });
+    let average_online = (!samples.is_empty())
+        .then(|| round1(samples.iter().map(|s| s.online as f64).sum::<f64>() / samples.len() as f64));
+
+    Json(PopulationHistory {
+        realm: realm.id,
+        days,
+        from,
+        to,
+        samples: samples.len() as u32,
+        peak,
+        average_online,
+        uptime_percent: uptime_percent(&intervals, from, to),
+        daily,
+    }).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uptime_covers_only_the_window() {
+        // Up for the whole second half, plus an interval ending before the window.
+        let intervals = [(0, 50), (150, 300)];
+        assert_eq!(uptime_percent(&intervals, 100, 200), 50.0);
+    }
+
+    #[test]
+    fn uptime_rounds_to_two_decimals() {
+        assert_eq!(uptime_percent(&[(0, 1)], 0, 3), 33.33);
+        assert_eq!(uptime_percent(&[(0, 2)], 0, 3), 66.67);
+    }
+
+    #[test]
+    fn uptime_of_empty_window_is_zero() {
+        assert_eq!(uptime_percent(&[(0, 100)], 50, 50), 0.0);
+        assert_eq!(uptime_percent(&[], 0, 100), 0.0);
+    }
+}
@@ -0,0 +1,303 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use futures::{future::BoxFuture, TryStreamExt};
+use image::{
+    codecs::jpeg::JpegEncoder,
+    imageops::FilterType,
+    io::{Limits, Reader},
+    ImageError, ImageFormat,
+};
+use mongodb::{bson::doc, options::FindOneAndUpdateOptions, Collection};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use crate::{
+    AppState,
+    clock::now_secs,
+    character_services::load_owned_character,
+    handlers::{authenticate, caller_game_accounts},
+    models::CharacterPortrait,
+    realms::RealmDb,
+};
+
+/// Same limit the Node server's multer config used.
+pub const MAX_UPLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// Square thumbnails generated for every upload: (label, edge in pixels).
+const THUMBNAIL_SIZES: &[(&str, u32)] = &[("small", 64), ("medium", 256), ("large", 512)];
+
+/// Where processed portraits end up. Implementations return the public URL
+/// the stored object can be fetched from.
+pub trait PortraitStorage: Send + Sync {
+    fn put(&self, key: String, bytes: Vec<u8>) -> BoxFuture<'_, Result<String, String>>;
+    fn delete(&self, key: String) -> BoxFuture<'_, Result<(), String>>;
+}
+
+pub struct LocalDiskStorage {
+    root: PathBuf,
+    public_prefix: String,
+}
+
+impl LocalDiskStorage {
+    pub fn new(root: impl Into<PathBuf>, public_prefix: impl Into<String>) -> Self {
+        LocalDiskStorage {
+            root: root.into(),
+            public_prefix: public_prefix.into(),
+        }
+    }
+}
+
+impl PortraitStorage for LocalDiskStorage {
+    fn put(&self, key: String, bytes: Vec<u8>) -> BoxFuture<'_, Result<String, String>> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(&self.root).await.map_err(|e| e.to_string())?;
+            tokio::fs::write(self.root.join(&key), bytes).await.map_err(|e| e.to_string())?;
+            Ok(format!("{}/{}", self.public_prefix.trim_end_matches('/'), key))
+        })
+    }
+
+    fn delete(&self, key: String) -> BoxFuture<'_, Result<(), String>> {
+        Box::pin(async move {
+            match tokio::fs::remove_file(self.root.join(&key)).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+                _ => Ok(()),
+            }
+        })
+    }
+}
+
+/// Builds the storage backend selected by `PORTRAIT_STORAGE`.
+pub fn storage_from_env() -> Result<Box<dyn PortraitStorage>, String> {
+    match std::env::var("PORTRAIT_STORAGE").unwrap_or_else(|_| "local".to_string()).as_str() {
+        "local" => {
+            let root = std::env::var("PORTRAIT_DIR").unwrap_or_else(|_| "uploads/characters".to_string());
+            Ok(Box::new(LocalDiskStorage::new(root, "/api/uploads/characters")))
+        },
+        other => Err(format!("Unknown PORTRAIT_STORAGE backend: {}", other)),
+    }
+}
+
+/// Storage key of one thumbnail. Characters are identified by realm and guid
+/// since names are reused after renames and deletions, and the version
+/// changes the URL of every new upload.
+fn portrait_key(realm: u32, guid: u32, version: &str, label: &str) -> String {
+    format!("{}_{}_{}_{}.jpg", realm, guid, version, label)
+}
+
+fn portrait_version(bytes: &[u8]) -> String {
+    hex::encode(Sha1::digest(bytes))[..16].to_string()
+}
+
+/// Largest image decoded, per side, and the memory its decoder may use. A
+/// small compressed upload can otherwise expand to hundreds of MiB.
+const MAX_IMAGE_EDGE: u32 = 4096;
+const MAX_DECODE_BYTES: u64 = 64 << 20;
+
+/// Encoded JPEG per thumbnail label.
+type Thumbnails = Vec<(&'static str, Vec<u8>)>;
+
+/// Decodes the upload and renders every thumbnail size as JPEG. The format is
+/// sniffed from the bytes, not taken from the client's content type.
+fn render_thumbnails(bytes: &[u8]) -> Result<Thumbnails, (StatusCode, String)> {
+    let format = image::guess_format(bytes)
+        .map_err(|_| (StatusCode::UNSUPPORTED_MEDIA_TYPE, "Unrecognised image data".to_string()))?;
+    if !matches!(format, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP) {
+        return Err((StatusCode::UNSUPPORTED_MEDIA_TYPE, "Only JPEG, PNG and WebP images are accepted".to_string()));
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_EDGE);
+    limits.max_image_height = Some(MAX_IMAGE_EDGE);
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+    let mut reader = Reader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+    let source = reader.decode().map_err(|e| match e {
+        ImageError::Limits(_) => (
+            StatusCode::BAD_REQUEST,
+            format!("Image must be at most {}x{} pixels", MAX_IMAGE_EDGE, MAX_IMAGE_EDGE),
+        ),
+        e => (StatusCode::UNSUPPORTED_MEDIA_TYPE, format!("Invalid image: {}", e)),
+    })?;
+
+    let mut outputs = Vec::new();
+    for (label, edge) in THUMBNAIL_SIZES {
+        let resized = source.resize_to_fill(*edge, *edge, FilterType::Lanczos3).to_rgb8();
+        let mut encoded = Vec::new();
+        JpegEncoder::new_with_quality(&mut encoded, 85)
+            .encode_image(&resized)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        outputs.push((*label, encoded));
+    }
+    Ok(outputs)
+}
+
+pub async fn upload_character_image(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    let accounts = match caller_game_accounts(&state, &claims).await {
+        Ok(a) => a,
+        Err(e) => return e.into_response(),
+    };
+    let character = match load_owned_character(&realm.pool, &claims, &accounts, &name, false).await {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    let mut upload = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("image") => {
+                match field.bytes().await {
+                    Ok(bytes) => upload = Some(bytes),
+                    Err(_) => return (StatusCode::PAYLOAD_TOO_LARGE, "Image must be 5MB or smaller").into_response(),
+                }
+                break;
+            },
+            Ok(Some(_)) => continue,
+            Ok(None) => break,
+            Err(_) => return (StatusCode::BAD_REQUEST, "Invalid multipart body").into_response(),
+        }
+    }
+
+    let bytes = match upload {
+        Some(b) if !b.is_empty() => b,
+        _ => return (StatusCode::BAD_REQUEST, "Missing image file").into_response(),
+    };
+    if bytes.len() > MAX_UPLOAD_BYTES {
+        return (StatusCode::PAYLOAD_TOO_LARGE, "Image must be 5MB or smaller").into_response();
+    }
+
+    let version = portrait_version(&bytes);
+    let thumbnails = match tokio::task::spawn_blocking(move || render_thumbnails(&bytes)).await {
+        Ok(Ok(t)) => t,
+        Ok(Err(e)) => return e.into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Image processing failed").into_response(),
+    };
+
+    let mut urls: HashMap<String, String> = HashMap::new();
+    for (label, data) in thumbnails {
+        match state.portraits.put(portrait_key(realm.id, character.guid, &version, label), data).await {
+            Ok(url) => { urls.insert(label.to_string(), url); },
+            Err(e) => {
+                tracing::error!("Failed to store portrait for {}: {}", name, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to store image").into_response();
+            }
+        }
+    }
+
+    let portrait = CharacterPortrait {
+        realm: realm.id,
+        guid: character.guid,
+        name: name.clone(),
+        urls: urls.clone(),
+        version: version.clone(),
+        updated_at: now_secs(),
+    };
+    let collection: Collection<CharacterPortrait> = state.mongo.collection("character_portraits");
+    let document = match mongodb::bson::to_document(&portrait) {
+        Ok(d) => d,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to save image").into_response(),
+    };
+    let previous = match collection.find_one_and_update(
+        doc! { "realm": realm.id, "guid": character.guid },
+        doc! { "$set": document },
+        FindOneAndUpdateOptions::builder().upsert(true).build(),
+    ).await {
+        Ok(p) => p,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to save image").into_response(),
+    };
+
+    // Files of the replaced upload.
+    if let Some(old) = previous.map(|p| p.version).filter(|v| *v != version) {
+        for (label, _) in THUMBNAIL_SIZES {
+            if let Err(e) = state.portraits.delete(portrait_key(realm.id, character.guid, &old, label)).await {
+                tracing::warn!("Failed to remove old portrait of {}: {}", name, e);
+            }
+        }
+    }
+
+    (StatusCode::CREATED, Json(serde_json::json!({
+        "ok": true,
+        "imageUrl": urls.get("medium"),
+        "sizes": urls,
+        "version": version,
+    }))).into_response()
+}
+
+/// Medium portrait URL per character guid, for decorating ranking rows.
+pub async fn portrait_urls(state: &AppState, realm: &RealmDb, guids: &[u32]) -> HashMap<u32, String> {
+    let collection: Collection<CharacterPortrait> = state.mongo.collection("character_portraits");
+    let mut urls = HashMap::new();
+    if guids.is_empty() {
+        return urls;
+    }
+
+    let cursor = match collection.find(doc! { "realm": realm.id, "guid": { "$in": guids.to_vec() } }, None).await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("Failed to load character portraits: {}", e);
+            return urls;
+        }
+    };
+
+    match cursor.try_collect::<Vec<CharacterPortrait>>().await {
+        Ok(portraits) => {
+            for p in portraits {
+                if let Some(url) = p.urls.get("medium") {
+                    urls.insert(p.guid, url.clone());
+                }
+            }
+        },
+        Err(e) => tracing::warn!("Failed to load character portraits: {}", e),
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::RgbImage::new(width, height)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn renders_every_thumbnail_size() {
+        let thumbnails = render_thumbnails(&png(40, 30)).unwrap();
+
+        let labels: Vec<&str> = thumbnails.iter().map(|(label, _)| *label).collect();
+        assert_eq!(labels, vec!["small", "medium", "large"]);
+        let large = image::load_from_memory(&thumbnails[2].1).unwrap();
+        assert_eq!((large.width(), large.height()), (512, 512));
+    }
+
+    #[test]
+    fn rejects_images_over_the_size_limit() {
+        let (status, _) = render_thumbnails(&png(MAX_IMAGE_EDGE + 1, 1)).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn rejects_unsupported_data() {
+        let (status, _) = render_thumbnails(b"GIF89a not really").unwrap_err();
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
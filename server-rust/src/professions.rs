@@ -0,0 +1,134 @@
+use axum::{
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder, Row};
+
+use crate::{realms::RealmDb, domain::{CharacterTraits, Faction, Locale}, models::{CharacterProfession, ProfessionRankingEntry}};
+
+/// Profession skill ids from SkillLine.dbc: (id, slug, name).
+pub const PROFESSIONS: &[(u16, &str, &str)] = &[
+    (171, "alchemy", "Alchemy"),
+    (164, "blacksmithing", "Blacksmithing"),
+    (333, "enchanting", "Enchanting"),
+    (202, "engineering", "Engineering"),
+    (182, "herbalism", "Herbalism"),
+    (773, "inscription", "Inscription"),
+    (755, "jewelcrafting", "Jewelcrafting"),
+    (165, "leatherworking", "Leatherworking"),
+    (186, "mining", "Mining"),
+    (393, "skinning", "Skinning"),
+    (197, "tailoring", "Tailoring"),
+    (185, "cooking", "Cooking"),
+    (129, "first-aid", "First Aid"),
+    (356, "fishing", "Fishing"),
+];
+
+fn profession_by_slug(slug: &str) -> Option<(u16, &'static str, &'static str)> {
+    PROFESSIONS.iter().copied().find(|(_, s, _)| *s == slug)
+}
+
+fn profession_by_id(id: u16) -> Option<(u16, &'static str, &'static str)> {
+    PROFESSIONS.iter().copied().find(|(i, _, _)| *i == id)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProfessionRankingQuery {
+    pub faction: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// Professions known by a character, highest skill first.
+pub async fn character_professions(pool: &sqlx::MySqlPool, guid: u32) -> Result<Vec<CharacterProfession>, sqlx::Error> {
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new("SELECT skill, value, max FROM character_skills WHERE guid = ");
+    builder.push_bind(guid).push(" AND skill IN (");
+    let mut separated = builder.separated(", ");
+    for (id, _, _) in PROFESSIONS {
+        separated.push_bind(*id);
+    }
+    builder.push(") ORDER BY value DESC");
+
+    let rows = builder.build().fetch_all(pool).await?;
+    Ok(rows.iter().filter_map(|row| {
+        let skill = row.try_get::<u16, _>("skill").unwrap_or_default();
+        let (id, slug, name) = profession_by_id(skill)?;
+        Some(CharacterProfession {
+            id,
+            slug: slug.to_string(),
+            name: name.to_string(),
+            value: row.try_get::<u16, _>("value").unwrap_or_default(),
+            max: row.try_get::<u16, _>("max").unwrap_or_default(),
+        })
+    }).collect())
+}
+
+pub async fn list_professions() -> impl IntoResponse {
+    let professions: Vec<serde_json::Value> = PROFESSIONS.iter().map(|(id, slug, name)| {
+        serde_json::json!({ "id": id, "slug": slug, "name": name })
+    }).collect();
+
+    Json(professions)
+}
+
+pub async fn get_profession_ranking(
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(slug): Path<String>,
+    Query(params): Query<ProfessionRankingQuery>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+    let (skill, slug, profession) = match profession_by_slug(&slug) {
+        Some(p) => p,
+        None => return (StatusCode::NOT_FOUND, "Unknown profession").into_response(),
+    };
+    let limit = params.limit.unwrap_or(50).clamp(1, 100);
+
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT c.name, c.race, c.class, c.gender, c.level, s.value, s.max \
+         FROM character_skills s JOIN characters c ON c.guid = s.guid \
+         WHERE c.account <> 0 AND s.skill = ",
+    );
+    builder.push_bind(skill);
+
+    if let Some(faction) = &params.faction {
+        match Faction::from_slug(faction) {
+            Some(f) => { builder.push(format!(" AND c.race IN ({})", f.race_ids_sql())); },
+            None => return (StatusCode::BAD_REQUEST, "Faction must be alliance or horde").into_response(),
+        }
+    }
+
+    builder.push(" ORDER BY s.value DESC, c.level DESC, c.name ASC LIMIT ").push_bind(limit);
+
+    let rows = match builder.build().fetch_all(&realm.pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to load {} ranking: {}", slug, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let ranking: Vec<ProfessionRankingEntry> = rows.iter().enumerate().map(|(i, row)| {
+        ProfessionRankingEntry {
+            rank: i + 1,
+            name: row.try_get::<String, _>("name").unwrap_or_default(),
+            traits: CharacterTraits::new(
+                row.try_get::<u8, _>("race").unwrap_or_default(),
+                row.try_get::<u8, _>("class").unwrap_or_default(),
+                row.try_get::<u8, _>("gender").ok(),
+                locale,
+            ),
+            level: row.try_get::<u8, _>("level").unwrap_or_default(),
+            value: row.try_get::<u16, _>("value").unwrap_or_default(),
+            max: row.try_get::<u16, _>("max").unwrap_or_default(),
+        }
+    }).collect();
+
+    Json(serde_json::json!({
+        "profession": profession,
+        "slug": slug,
+        "ranking": ranking,
+    })).into_response()
+}
@@ -0,0 +1,217 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, from_document},
+    options::{FindOneOptions, FindOptions},
+    Collection, IndexModel,
+};
+use serde::Deserialize;
+use sqlx::Row;
+use std::collections::HashMap;
+
+use crate::{
+    AppState,
+    clock::{interval_from_env, now_secs},
+    armory::find_character_guid,
+    models::CharacterSnapshot,
+    realms::RealmDb,
+    retention,
+};
+
+const WEEK_SECS: i64 = 7 * 24 * 3600;
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub days: Option<i64>,
+}
+
+/// Starts the background task that snapshots every character's progress on
+/// every realm. Snapshots are kept for `PROGRESS_RETENTION_DAYS` (400 by
+/// default). `PROGRESS_SNAPSHOT_INTERVAL_SECS=0` disables it.
+pub fn spawn_snapshotter(state: AppState) {
+    let Some(period) = interval_from_env("PROGRESS_SNAPSHOT_INTERVAL_SECS", 3600) else {
+        tracing::info!("Progress snapshots disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let collection: Collection<CharacterSnapshot> = state.mongo.collection("character_snapshots");
+        let index = IndexModel::builder().keys(doc! { "realm": 1, "guid": 1, "at": 1 }).build();
+        if let Err(e) = collection.create_index(index, None).await {
+            tracing::warn!("Failed to create character_snapshots index: {}", e);
+        }
+        let retention_days = retention::retention_days("PROGRESS_RETENTION_DAYS", 400);
+        if let Err(e) = retention::ensure_ttl(&collection, retention_days).await {
+            tracing::warn!("Failed to set up character_snapshots retention: {}", e);
+        }
+
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            for (realm, pool) in state.realms.all() {
+                match snapshot(&state, realm, &pool, retention_days).await {
+                    Ok(count) => tracing::info!("Recorded progress snapshots for {} characters on realm {}", count, realm),
+                    Err(e) => tracing::warn!("Progress snapshot failed for realm {}: {}", realm, e),
+                }
+            }
+        }
+    });
+}
+
+/// Latest snapshot of every character of the realm.
+async fn latest_snapshots(
+    collection: &Collection<CharacterSnapshot>,
+    realm: u32,
+) -> Result<HashMap<u32, CharacterSnapshot>, Box<dyn std::error::Error + Send + Sync>> {
+    let pipeline = vec![
+        doc! { "$match": { "realm": realm } },
+        doc! { "$sort": { "guid": 1, "at": -1 } },
+        doc! { "$group": { "_id": "$guid", "latest": { "$first": "$$ROOT" } } },
+        doc! { "$replaceRoot": { "newRoot": "$latest" } },
+    ];
+    let mut latest = HashMap::new();
+    let mut cursor = collection.aggregate(pipeline, None).await?;
+    while let Some(document) = cursor.try_next().await? {
+        let snapshot: CharacterSnapshot = from_document(document)?;
+        latest.insert(snapshot.guid, snapshot);
+    }
+    Ok(latest)
+}
+
+/// Whether a snapshot records anything its predecessor didn't.
+fn changed(previous: Option<&CharacterSnapshot>, current: &CharacterSnapshot) -> bool {
+    previous.is_none_or(|p| {
+        (&p.name, p.level, p.xp, p.money, p.total_time, p.achievement_points)
+            != (&current.name, current.level, current.xp, current.money, current.total_time, current.achievement_points)
+    })
+}
+
+/// Snapshots the realm's characters, skipping the ones that haven't changed
+/// since their last snapshot: idle characters would otherwise add a
+/// duplicate per run.
+async fn snapshot(state: &AppState, realm: u32, pool: &sqlx::MySqlPool, retention_days: i64) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let characters = sqlx::query("SELECT guid, name, level, xp, money, totaltime FROM characters WHERE account <> 0")
+        .fetch_all(pool)
+        .await?;
+
+    if characters.is_empty() {
+        return Ok(0);
+    }
+
+    let mut points: HashMap<u32, u32> = HashMap::new();
+    for row in sqlx::query("SELECT guid, achievement FROM character_achievement").fetch_all(pool).await? {
+        let guid = row.try_get::<u32, _>("guid").unwrap_or_default();
+        let achievement = row.try_get::<u16, _>("achievement").unwrap_or_default() as u32;
+        *points.entry(guid).or_insert(0) += state.achievements.points(achievement);
+    }
+
+    let collection: Collection<CharacterSnapshot> = state.mongo.collection("character_snapshots");
+    let latest = latest_snapshots(&collection, realm).await?;
+
+    let at = now_secs();
+    let snapshots: Vec<CharacterSnapshot> = characters.iter().map(|row| {
+        let guid = row.try_get::<u32, _>("guid").unwrap_or_default();
+        CharacterSnapshot {
+            id: None,
+            realm,
+            guid,
+            name: row.try_get::<String, _>("name").unwrap_or_default(),
+            at,
+            level: row.try_get::<u8, _>("level").unwrap_or_default(),
+            xp: row.try_get::<u32, _>("xp").unwrap_or_default(),
+            money: row.try_get::<u32, _>("money").unwrap_or_default(),
+            total_time: row.try_get::<u32, _>("totaltime").unwrap_or_default(),
+            achievement_points: points.get(&guid).copied().unwrap_or(0),
+            expires_at: retention::expires_at(at, retention_days),
+        }
+    }).filter(|s| changed(latest.get(&s.guid), s)).collect();
+
+    let count = snapshots.len();
+    if !snapshots.is_empty() {
+        collection.insert_many(snapshots, None).await?;
+    }
+    Ok(count)
+}
+
+pub async fn get_character_history(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    Path(name): Path<String>,
+    Query(params): Query<HistoryQuery>,
+) -> impl IntoResponse {
+    let guid = match find_character_guid(&realm.pool, &name).await {
+        Ok(Some(guid)) => guid,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Character not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to look up character {}: {}", name, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+
+    let days = params.days.unwrap_or(30).clamp(1, 365);
+    let now = now_secs();
+    let since = now - days * 24 * 3600;
+    let window_start = since.min(now - WEEK_SECS);
+
+    // Unchanged characters aren't snapshotted again, so the state entering the
+    // window is the last snapshot before it.
+    let collection: Collection<CharacterSnapshot> = state.mongo.collection("character_snapshots");
+    let before = match collection.find_one(
+        doc! { "realm": realm.id, "guid": guid, "at": { "$lt": window_start } },
+        FindOneOptions::builder().sort(doc! { "at": -1 }).build(),
+    ).await {
+        Ok(s) => s,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+    let cursor = match collection.find(
+        doc! { "realm": realm.id, "guid": guid, "at": { "$gte": window_start } },
+        FindOptions::builder().sort(doc! { "at": 1 }).build(),
+    ).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    let mut snapshots: Vec<CharacterSnapshot> = match cursor.try_collect().await {
+        Ok(s) => s,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+    snapshots.splice(0..0, before);
+
+    // Weekly deltas compare the state a week ago with the latest snapshot.
+    let week_start = snapshots.iter().rev().find(|s| s.at <= now - WEEK_SECS)
+        .or_else(|| snapshots.iter().find(|s| s.at >= now - WEEK_SECS));
+    let week_summary = match (week_start, snapshots.last()) {
+        (Some(first), Some(last)) => serde_json::json!({
+            "levelsGained": last.level.saturating_sub(first.level),
+            "moneyChange": last.money as i64 - first.money as i64,
+            "playtime": last.total_time.saturating_sub(first.total_time),
+            "achievementPointsGained": last.achievement_points.saturating_sub(first.achievement_points),
+        }),
+        _ => serde_json::Value::Null,
+    };
+
+    // The series starts with the state entering the requested window.
+    let first = snapshots.iter().rposition(|s| s.at < since).unwrap_or(0);
+    let series: Vec<serde_json::Value> = snapshots[first..].iter().map(|s| {
+        serde_json::json!({
+            "at": s.at,
+            "level": s.level,
+            "xp": s.xp,
+            "money": s.money,
+            "totalTime": s.total_time,
+            "achievementPoints": s.achievement_points,
+        })
+    }).collect();
+
+    Json(serde_json::json!({
+        "name": name,
+        "days": days,
+        "series": series,
+        "week": week_summary,
+    })).into_response()
+}
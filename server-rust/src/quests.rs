@@ -0,0 +1,79 @@
+use sqlx::{MySql, QueryBuilder, Row};
+use std::collections::HashMap;
+
+use crate::models::{CharacterQuest, CharacterQuestLog};
+
+/// Quest ids per `quest_template` lookup; a level 80 character has thousands
+/// of rewarded quests.
+const TEMPLATE_CHUNK: usize = 1000;
+
+fn status_slug(status: u8) -> &'static str {
+    match status {
+        1 => "complete",
+        5 => "failed",
+        _ => "incomplete",
+    }
+}
+
+/// Titles and levels from `quest_template` in the world database.
+async fn quest_templates(world_pool: &sqlx::MySqlPool, ids: &[u32]) -> Result<HashMap<u32, (String, i16)>, sqlx::Error> {
+    let mut templates = HashMap::new();
+    for chunk in ids.chunks(TEMPLATE_CHUNK) {
+        let mut builder: QueryBuilder<MySql> = QueryBuilder::new("SELECT ID, LogTitle, QuestLevel FROM quest_template WHERE ID IN (");
+        let mut separated = builder.separated(", ");
+        for id in chunk {
+            separated.push_bind(*id);
+        }
+        builder.push(")");
+
+        for row in builder.build().fetch_all(world_pool).await? {
+            templates.insert(
+                row.try_get::<u32, _>("ID").unwrap_or_default(),
+                (
+                    row.try_get::<String, _>("LogTitle").unwrap_or_default(),
+                    row.try_get::<i16, _>("QuestLevel").unwrap_or_default(),
+                ),
+            );
+        }
+    }
+    Ok(templates)
+}
+
+/// Rewarded quests plus the character's quest log.
+pub async fn character_quests(
+    char_pool: &sqlx::MySqlPool,
+    world_pool: &sqlx::MySqlPool,
+    guid: u32,
+) -> Result<CharacterQuestLog, sqlx::Error> {
+    let rewarded: Vec<u32> = sqlx::query_scalar("SELECT quest FROM character_queststatus_rewarded WHERE guid = ? ORDER BY quest")
+        .bind(guid)
+        .fetch_all(char_pool)
+        .await?;
+
+    let rows = sqlx::query("SELECT quest, status FROM character_queststatus WHERE guid = ? AND status <> 0")
+        .bind(guid)
+        .fetch_all(char_pool)
+        .await?;
+    let active: Vec<(u32, u8)> = rows.iter().map(|row| (
+        row.try_get::<u32, _>("quest").unwrap_or_default(),
+        row.try_get::<u8, _>("status").unwrap_or_default(),
+    )).collect();
+
+    let ids: Vec<u32> = rewarded.iter().copied().chain(active.iter().map(|(id, _)| *id)).collect();
+    let templates = quest_templates(world_pool, &ids).await?;
+    let quest = |id: u32, status: &str| {
+        let template = templates.get(&id);
+        CharacterQuest {
+            id,
+            title: template.map(|(t, _)| t.clone()),
+            level: template.map(|(_, l)| *l),
+            status: status.to_string(),
+        }
+    };
+
+    Ok(CharacterQuestLog {
+        completed: rewarded.len() as u32,
+        completed_quests: rewarded.iter().map(|id| quest(*id, "rewarded")).collect(),
+        active: active.iter().map(|(id, status)| quest(*id, status_slug(*status))).collect(),
+    })
+}
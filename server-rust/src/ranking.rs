@@ -0,0 +1,113 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder, Row};
+
+use crate::{
+    AppState,
+    domain::{CharacterTraits, Locale},
+    gear::{cached_gear, top_by},
+    models::RankingEntry,
+    portraits::portrait_urls,
+    realms::RealmDb,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct TopQuery {
+    pub limit: Option<u32>,
+    /// level (default), gearscore or itemlevel.
+    pub sort: Option<String>,
+}
+
+pub async fn get_top_characters(
+    State(state): State<AppState>,
+    realm: RealmDb,
+    headers: HeaderMap,
+    Query(params): Query<TopQuery>,
+) -> impl IntoResponse {
+    let locale = Locale::from_headers(&headers);
+    let limit = params.limit.unwrap_or(10).clamp(1, 50);
+
+    let gear_field = match params.sort.as_deref().unwrap_or("level") {
+        "level" => None,
+        "gearscore" => Some("gearScore"),
+        "itemlevel" => Some("averageItemLevel"),
+        _ => return (StatusCode::BAD_REQUEST, "Sort must be level, gearscore or itemlevel").into_response(),
+    };
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(
+        "SELECT c.guid, c.name, c.race, c.class, c.gender, c.level, c.totaltime, g.name AS guildName \
+         FROM characters c \
+         LEFT JOIN guild_member gm ON gm.guid = c.guid \
+         LEFT JOIN guild g ON g.guildid = gm.guildid \
+         WHERE c.account <> 0",
+    );
+
+    // Gear rankings come from the cached scores; the characters query only
+    // decorates them and drops entries for deleted characters.
+    let gear_order: Vec<u32> = match gear_field {
+        Some(field) => match top_by(&state, &realm, field, limit as i64).await {
+            Ok(top) => top.iter().map(|g| g.guid).collect(),
+            Err(e) => {
+                tracing::error!("Failed to load gear ranking: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        },
+        None => Vec::new(),
+    };
+    if gear_field.is_some() {
+        if gear_order.is_empty() {
+            return Json(Vec::<RankingEntry>::new()).into_response();
+        }
+        builder.push(" AND c.guid IN (");
+        let mut separated = builder.separated(", ");
+        for guid in &gear_order {
+            separated.push_bind(*guid);
+        }
+        builder.push(")");
+    } else {
+        builder.push(" ORDER BY c.level DESC, c.totaltime DESC LIMIT ").push_bind(limit);
+    }
+
+    let mut rows = match builder.build().fetch_all(&realm.pool).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::error!("Failed to load top characters: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    };
+    if gear_field.is_some() {
+        rows.sort_by_key(|row| {
+            let guid = row.try_get::<u32, _>("guid").unwrap_or_default();
+            gear_order.iter().position(|g| *g == guid)
+        });
+    }
+
+    let guids: Vec<u32> = rows.iter().map(|row| row.try_get::<u32, _>("guid").unwrap_or_default()).collect();
+    let mut images = portrait_urls(&state, &realm, &guids).await;
+    let gear = cached_gear(&state, &realm, &guids).await;
+
+    let ranking: Vec<RankingEntry> = rows.iter().map(|row| {
+        let guid = row.try_get::<u32, _>("guid").unwrap_or_default();
+        RankingEntry {
+            name: row.try_get::<String, _>("name").unwrap_or_default(),
+            traits: CharacterTraits::new(
+                row.try_get::<u8, _>("race").unwrap_or_default(),
+                row.try_get::<u8, _>("class").unwrap_or_default(),
+                row.try_get::<u8, _>("gender").ok(),
+                locale,
+            ),
+            level: row.try_get::<u8, _>("level").unwrap_or_default(),
+            total_time: row.try_get::<u32, _>("totaltime").unwrap_or_default(),
+            guild_name: row.try_get::<Option<String>, _>("guildName").unwrap_or_default(),
+            image_url: images.remove(&guid),
+            average_item_level: gear.get(&guid).map(|g| g.average_item_level),
+            gear_score: gear.get(&guid).map(|g| g.gear_score),
+        }
+    }).collect();
+
+    Json(ranking).into_response()
+}
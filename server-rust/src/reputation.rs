@@ -0,0 +1,177 @@
+use sqlx::Row;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{
+    dbc::DbcFile,
+    domain::{Locale, ReputationRank},
+    models::CharacterReputation,
+};
+
+/// Field count and indexes of Faction.dbc in 3.3.5a. The base reputation
+/// columns are four (race mask, class mask, value) entries.
+const FACTION_FIELDS: usize = 57;
+const FACTION_BASE_RACE_MASK: usize = 2;
+const FACTION_BASE_CLASS_MASK: usize = 6;
+const FACTION_BASE_VALUE: usize = 10;
+/// First of the 16 `Name_lang` columns, enUS first.
+const FACTION_NAME: usize = 23;
+const FACTION_NAME_LOCALES: usize = 16;
+
+/// `character_reputation.flags` bit for factions shown in the reputation pane.
+const FACTION_FLAG_VISIBLE: u16 = 0x01;
+
+#[derive(Debug, Default)]
+struct FactionMeta {
+    /// Name per `Name_lang` column; the server's tables usually fill one.
+    names: Vec<Option<String>>,
+    /// (race mask, class mask, value), checked in order.
+    base: Vec<(u32, u32, i32)>,
+}
+
+/// Faction names and starting reputations from Faction.dbc.
+/// `character_reputation.standing` only holds what a character gained on
+/// top of the starting value of its race and class.
+#[derive(Debug, Default)]
+pub struct FactionCatalog {
+    factions: HashMap<u16, FactionMeta>,
+}
+
+impl FactionCatalog {
+    pub fn from_dbc(dir: &Path) -> Result<Self, String> {
+        let factions = DbcFile::open(dir, "Faction.dbc", FACTION_FIELDS)?
+            .records()
+            .map(|record| {
+                let meta = FactionMeta {
+                    names: (0..FACTION_NAME_LOCALES).map(|l| record.string(FACTION_NAME + l)).collect(),
+                    base: (0..4).map(|i| (
+                        record.u32(FACTION_BASE_RACE_MASK + i),
+                        record.u32(FACTION_BASE_CLASS_MASK + i),
+                        record.u32(FACTION_BASE_VALUE + i) as i32,
+                    )).collect(),
+                };
+                (record.u32(0) as u16, meta)
+            })
+            .collect();
+        Ok(FactionCatalog { factions })
+    }
+
+    pub fn len(&self) -> usize {
+        self.factions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.factions.is_empty()
+    }
+
+    /// Name in the dashboard language. The 3.3.5a client has no Portuguese
+    /// locale, so Portuguese, like a missing translation, uses whichever
+    /// language the server's tables were extracted in.
+    fn name(&self, id: u16, locale: Locale) -> Option<&str> {
+        let names = &self.factions.get(&id)?.names;
+        let column = match locale {
+            Locale::En => Some(0),
+            Locale::PtPt | Locale::PtBr => None,
+        };
+        column.and_then(|c| names.get(c)?.as_deref())
+            .or_else(|| names.iter().find_map(|n| n.as_deref()))
+    }
+
+    /// Starting standing of a race and class with a faction, picked the way
+    /// the worldserver does: the first entry whose masks match, an empty
+    /// mask matching everyone.
+    fn base_standing(&self, id: u16, race: u8, class: u8) -> i32 {
+        let mask = |id: u8| (id as u32).checked_sub(1).and_then(|shift| 1u32.checked_shl(shift)).unwrap_or(0);
+        let (race_mask, class_mask) = (mask(race), mask(class));
+        self.factions.get(&id)
+            .and_then(|f| f.base.iter().find(|(races, classes, _)| {
+                (*races == 0 || races & race_mask != 0) && (*classes == 0 || classes & class_mask != 0)
+            }))
+            .map(|(_, _, value)| *value)
+            .unwrap_or(0)
+    }
+}
+
+/// Visible faction standings for a character, highest first, including the
+/// starting reputation of its race and class.
+pub async fn character_reputations(
+    pool: &sqlx::MySqlPool,
+    catalog: &FactionCatalog,
+    guid: u32,
+    race: u8,
+    class: u8,
+    locale: Locale,
+) -> Result<Vec<CharacterReputation>, sqlx::Error> {
+    let rows = sqlx::query("SELECT faction, standing, flags FROM character_reputation WHERE guid = ?")
+        .bind(guid)
+        .fetch_all(pool)
+        .await?;
+
+    let mut reputations: Vec<CharacterReputation> = rows.iter().filter_map(|row| {
+        let flags = row.try_get::<u16, _>("flags").unwrap_or_default();
+        if flags & FACTION_FLAG_VISIBLE == 0 {
+            return None;
+        }
+        let id = row.try_get::<u16, _>("faction").unwrap_or_default();
+        let name = catalog.name(id, locale)?;
+        let standing = catalog.base_standing(id, race, class) + row.try_get::<i32, _>("standing").unwrap_or_default();
+        let (rank, value, max) = ReputationRank::from_standing(standing);
+        Some(CharacterReputation {
+            id,
+            name: name.to_string(),
+            standing,
+            rank: rank.slug().to_string(),
+            rank_name: rank.name(locale).to_string(),
+            value,
+            max,
+        })
+    }).collect();
+    reputations.sort_by_key(|r| std::cmp::Reverse(r.standing));
+    Ok(reputations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HUMAN: u8 = 1;
+    const ORC: u8 = 2;
+    const WARRIOR: u8 = 1;
+    const DEATH_KNIGHT: u8 = 6;
+
+    fn catalog() -> FactionCatalog {
+        let stormwind = FactionMeta {
+            names: vec![Some("Stormwind".to_string())],
+            // Humans start Friendly, the other Alliance races Neutral and
+            // the Horde races Hated.
+            base: vec![(0x1, 0, 3100), (0x44d, 0, 0), (0x2b2, 0, -42000), (0, 0, 0)],
+        };
+        let ebon_blade = FactionMeta {
+            names: vec![None, None, Some("Chevaliers de la Lame d'ébène".to_string())],
+            base: vec![(0, 0x20, 3000), (0, 0, 0), (0, 0, 0), (0, 0, 0)],
+        };
+        FactionCatalog { factions: HashMap::from([(72, stormwind), (1098, ebon_blade)]) }
+    }
+
+    #[test]
+    fn base_standing_follows_race_and_class() {
+        let catalog = catalog();
+
+        assert_eq!(catalog.base_standing(72, HUMAN, WARRIOR), 3100);
+        assert_eq!(catalog.base_standing(72, ORC, WARRIOR), -42000);
+        assert_eq!(catalog.base_standing(1098, ORC, DEATH_KNIGHT), 3000);
+        // Only the catch-all entry matches.
+        assert_eq!(catalog.base_standing(1098, HUMAN, WARRIOR), 0);
+        assert_eq!(catalog.base_standing(999, HUMAN, WARRIOR), 0);
+    }
+
+    #[test]
+    fn name_falls_back_to_the_extracted_language() {
+        let catalog = catalog();
+
+        assert_eq!(catalog.name(72, Locale::En), Some("Stormwind"));
+        assert_eq!(catalog.name(72, Locale::PtBr), Some("Stormwind"));
+        assert_eq!(catalog.name(1098, Locale::En), Some("Chevaliers de la Lame d'ébène"));
+        assert_eq!(catalog.name(999, Locale::En), None);
+    }
+}
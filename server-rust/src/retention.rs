@@ -0,0 +1,45 @@
+//! Expiry of the time series kept in Mongo. Each sample carries an
+//! `expiresAt` date set when it is written and a TTL index removes it once
+//! that date has passed, so a changed retention only applies to new samples.
+
+use mongodb::{
+    bson::{doc, DateTime},
+    options::IndexOptions,
+    Collection, IndexModel,
+};
+use std::time::Duration;
+
+const DAY_SECS: i64 = 24 * 3600;
+
+/// Retention in days from `var`, or `default_days`. 0 keeps samples forever.
+pub fn retention_days(var: &str, default_days: i64) -> i64 {
+    std::env::var(var)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default_days)
+        .max(0)
+}
+
+/// Expiry date of a sample taken at `at` (unix seconds).
+pub fn expires_at(at: i64, days: i64) -> Option<DateTime> {
+    (days > 0).then(|| DateTime::from_millis((at + days * DAY_SECS).saturating_mul(1000)))
+}
+
+/// Creates the TTL index and dates the samples written before it existed
+/// from their `at` field.
+pub async fn ensure_ttl<T>(collection: &Collection<T>, days: i64) -> Result<(), mongodb::error::Error> {
+    let index = IndexModel::builder()
+        .keys(doc! { "expiresAt": 1 })
+        .options(IndexOptions::builder().expire_after(Duration::ZERO).build())
+        .build();
+    collection.create_index(index, None).await?;
+
+    if days > 0 {
+        collection.update_many(
+            doc! { "expiresAt": { "$exists": false } },
+            vec![doc! { "$set": { "expiresAt": { "$toDate": { "$multiply": [{ "$add": ["$at", days * DAY_SECS] }, 1000] } } } }],
+            None,
+        ).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,437 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    options::FindOptions,
+    Collection, IndexModel,
+};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    clock::{interval_from_env, now_secs},
+    handlers::authenticate,
+    models::{MaintenanceWindow, ServerSchedule},
+};
+
+/// Seconds before a scheduled restart at which players are warned in-game.
+const COUNTDOWN_OFFSETS: [i64; 6] = [3600, 1800, 900, 600, 300, 120];
+/// The worldserver runs its own countdown for the last stretch, so the
+/// restart command is sent this long before the scheduled time.
+const HANDOFF_SECS: i64 = 60;
+/// How long a failed hand-off keeps being retried past the scheduled time.
+const RETRY_GRACE_SECS: i64 = 300;
+const MAX_SCHEDULE_AHEAD_SECS: i64 = 30 * 24 * 3600;
+
+fn collection(state: &AppState) -> Collection<ServerSchedule> {
+    state.mongo.collection("server_schedules")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleRequest {
+    /// restart (default) or shutdown.
+    pub action: Option<String>,
+    /// Unix time of the restart; alternatively `inMinutes`.
+    pub at: Option<i64>,
+    #[serde(rename = "inMinutes")]
+    pub in_minutes: Option<i64>,
+    #[serde(rename = "durationMinutes")]
+    pub duration_minutes: Option<u32>,
+    pub reason: Option<String>,
+}
+
+fn schedule_json(schedule: &ServerSchedule) -> serde_json::Value {
+    serde_json::json!({
+        "id": schedule.id.map(|id| id.to_hex()),
+        "action": schedule.action,
+        "scheduledAt": schedule.scheduled_at,
+        "durationMinutes": schedule.duration_minutes,
+        "reason": schedule.reason,
+        "status": schedule.status,
+        "error": schedule.error,
+        "createdBy": schedule.created_by,
+        "createdAt": schedule.created_at,
+    })
+}
+
+fn format_remaining(secs: i64) -> String {
+    let minutes = ((secs + 59) / 60).max(1) as u64;
+    match minutes {
+        m if m >= 60 && m % 60 == 0 => format!("{} hour{}", m / 60, if m == 60 { "" } else { "s" }),
+        1 => "1 minute".to_string(),
+        m => format!("{} minutes", m),
+    }
+}
+
+fn countdown_message(schedule: &ServerSchedule, remaining: i64) -> String {
+    let mut message = format!("Server {} in {}", schedule.action, format_remaining(remaining));
+    if let Some(reason) = &schedule.reason {
+        message.push_str(": ");
+        message.push_str(reason);
+    }
+    message
+}
+
+/// Countdown offsets reached but not announced yet. After downtime several
+/// can be due at once; they are all marked but only one warning goes out.
+fn due_offsets(announced: &[i64], remaining: i64) -> Vec<i64> {
+    COUNTDOWN_OFFSETS.iter()
+        .copied()
+        .filter(|offset| remaining <= *offset && !announced.contains(offset))
+        .collect()
+}
+
+/// Planned restarts and shutdowns that haven't happened yet or whose
+/// maintenance window is still open, soonest first.
+pub async fn upcoming(state: &AppState) -> Vec<MaintenanceWindow> {
+    let now = now_secs();
+    let cursor = match collection(state).find(
+        doc! { "status": { "$in": ["pending", "handing_off", "started", "completed"] } },
+        FindOptions::builder().sort(doc! { "scheduledAt": 1 }).build(),
+    ).await {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("Failed to load server schedules: {}", e);
+            return Vec::new();
+        }
+    };
+    let schedules: Vec<ServerSchedule> = cursor.try_collect().await.unwrap_or_default();
+
+    schedules.into_iter().filter_map(|s| {
+        let ends_at = s.duration_minutes.map(|m| s.scheduled_at + m as i64 * 60);
+        if ends_at.unwrap_or(s.scheduled_at) < now {
+            return None;
+        }
+        Some(MaintenanceWindow {
+            action: s.action,
+            starts_at: s.scheduled_at,
+            ends_at,
+            reason: s.reason,
+        })
+    }).collect()
+}
+
+/// Starts the background task that announces and executes scheduled
+/// restarts. Schedules live in Mongo, so ones planned before a backend
+/// restart still run. `SCHEDULER_TICK_SECS=0` disables it.
+pub fn spawn_runner(state: AppState) {
+    let Some(period) = interval_from_env("SCHEDULER_TICK_SECS", 30) else {
+        tracing::info!("Restart scheduler disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let index = IndexModel::builder().keys(doc! { "status": 1, "scheduledAt": 1 }).build();
+        if let Err(e) = collection(&state).create_index(index, None).await {
+            tracing::warn!("Failed to create server_schedules index: {}", e);
+        }
+        // A hand-off interrupted by a backend restart is retried.
+        if let Err(e) = collection(&state).update_many(
+            doc! { "status": "handing_off" },
+            doc! { "$set": { "status": "pending" } },
+            None,
+        ).await {
+            tracing::warn!("Failed to reset interrupted hand-offs: {}", e);
+        }
+
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_due(&state).await {
+                tracing::warn!("Restart scheduler failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn run_due(state: &AppState) -> Result<(), mongodb::error::Error> {
+    // Once the scheduled time has passed the worldserver has gone down on its
+    // own countdown.
+    collection(state).update_many(
+        doc! { "status": "started", "scheduledAt": { "$lte": now_secs() } },
+        doc! { "$set": { "status": "completed" } },
+        None,
+    ).await?;
+
+    let pending: Vec<ServerSchedule> = collection(state)
+        .find(doc! { "status": "pending" }, None)
+        .await?
+        .try_collect()
+        .await?;
+
+    for schedule in pending {
+        let Some(id) = schedule.id else { continue };
+        let remaining = schedule.scheduled_at - now_secs();
+
+        if remaining <= HANDOFF_SECS {
+            hand_off(state, id, &schedule, remaining).await?;
+            continue;
+        }
+
+        let due = due_offsets(&schedule.announced, remaining);
+        if due.is_empty() {
+            continue;
+        }
+        if let Err(e) = state.soap.execute(&format!("announce {}", countdown_message(&schedule, remaining))).await {
+            tracing::warn!("Failed to announce scheduled {}: {}", schedule.action, e);
+            continue;
+        }
+        collection(state).update_one(
+            doc! { "_id": id },
+            doc! { "$addToSet": { "announced": { "$each": due } } },
+            None,
+        ).await?;
+    }
+    Ok(())
+}
+
+/// Sends `.server restart|shutdown <secs>`; the worldserver counts down the
+/// rest and stops on its own. The schedule is claimed first, so a cancel
+/// racing the runner either wins before the command is sent or finds the
+/// schedule started and cancels the worldserver countdown. Schedules missed
+/// by more than `RETRY_GRACE_SECS`, e.g. while the backend was down, are
+/// given up rather than restarting the server unannounced.
+async fn hand_off(state: &AppState, id: ObjectId, schedule: &ServerSchedule, remaining: i64) -> Result<(), mongodb::error::Error> {
+    if remaining <= -RETRY_GRACE_SECS {
+        tracing::error!("Scheduled {} missed its time, giving up", schedule.action);
+        let error = schedule.error.clone().unwrap_or_else(|| "Missed the scheduled time".to_string());
+        collection(state).update_one(
+            doc! { "_id": id, "status": "pending" },
+            doc! { "$set": { "status": "failed", "error": error } },
+            None,
+        ).await?;
+        return Ok(());
+    }
+
+    let claimed = collection(state).find_one_and_update(
+        doc! { "_id": id, "status": "pending" },
+        doc! { "$set": { "status": "handing_off" } },
+        None,
+    ).await?;
+    if claimed.is_none() {
+        return Ok(());
+    }
+
+    let update = match state.soap.execute(&format!("server {} {}", schedule.action, remaining.max(1))).await {
+        Ok(_) => {
+            tracing::info!("Scheduled {} handed off to the worldserver", schedule.action);
+            doc! { "$set": { "status": "started" }, "$unset": { "error": "" } }
+        },
+        Err(e) => {
+            tracing::warn!("Scheduled {} failed, retrying: {}", schedule.action, e);
+            doc! { "$set": { "status": "pending", "error": e.to_string() } }
+        }
+    };
+    collection(state).update_one(doc! { "_id": id, "status": "handing_off" }, update, None).await?;
+    Ok(())
+}
+
+pub async fn create_schedule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<ScheduleRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let action = payload.action.as_deref().unwrap_or("restart");
+    if action != "restart" && action != "shutdown" {
+        return (StatusCode::BAD_REQUEST, "Action must be restart or shutdown").into_response();
+    }
+
+    let now = now_secs();
+    let scheduled_at = match (payload.at, payload.in_minutes) {
+        (Some(at), None) => Some(at),
+        (None, Some(minutes)) => minutes.checked_mul(60).and_then(|secs| now.checked_add(secs)),
+        _ => return (StatusCode::BAD_REQUEST, "Provide either at or inMinutes").into_response(),
+    };
+    let Some(scheduled_at) = scheduled_at else {
+        return (StatusCode::BAD_REQUEST, "Schedule must be between 1 minute and 30 days ahead").into_response();
+    };
+    if scheduled_at < now + HANDOFF_SECS || scheduled_at > now + MAX_SCHEDULE_AHEAD_SECS {
+        return (StatusCode::BAD_REQUEST, "Schedule must be between 1 minute and 30 days ahead").into_response();
+    }
+
+    let reason = payload.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    if reason.is_some_and(|r| r.len() > 200 || r.contains(['\r', '\n'])) {
+        return (StatusCode::BAD_REQUEST, "Reason must be a single line of at most 200 characters").into_response();
+    }
+
+    let mut schedule = ServerSchedule {
+        id: None,
+        action: action.to_string(),
+        scheduled_at,
+        duration_minutes: payload.duration_minutes,
+        reason: reason.map(str::to_string),
+        status: "pending".to_string(),
+        announced: Vec::new(),
+        error: None,
+        created_by: claims.sub,
+        created_at: now,
+    };
+
+    match collection(&state).insert_one(&schedule, None).await {
+        Ok(result) => {
+            schedule.id = result.inserted_id.as_object_id();
+            tracing::info!("Server {} scheduled for {} by {}", schedule.action, scheduled_at, schedule.created_by);
+            (StatusCode::CREATED, Json(schedule_json(&schedule))).into_response()
+        },
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+}
+
+pub async fn list_schedules(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let cursor = match collection(&state).find(
+        doc! {},
+        FindOptions::builder().sort(doc! { "scheduledAt": -1 }).limit(50).build(),
+    ).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    match cursor.try_collect::<Vec<ServerSchedule>>().await {
+        Ok(schedules) => Json(schedules.iter().map(schedule_json).collect::<Vec<_>>()).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    }
+}
+
+pub async fn cancel_schedule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+    let oid = match ObjectId::parse_str(&id) {
+        Ok(oid) => oid,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Invalid schedule id").into_response(),
+    };
+
+    let schedule = match collection(&state).find_one_and_update(
+        doc! { "_id": oid, "status": "pending" },
+        doc! { "$set": { "status": "cancelled" } },
+        None,
+    ).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return cancel_started(&state, oid, &id, &claims.sub).await,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+
+    // Players who saw a countdown are told it is off.
+    if !schedule.announced.is_empty() {
+        if let Err(e) = state.soap.execute(&format!("announce Scheduled server {} cancelled", schedule.action)).await {
+            tracing::warn!("Failed to announce cancelled {}: {}", schedule.action, e);
+        }
+    }
+    tracing::info!("Scheduled server {} {} cancelled by {}", schedule.action, id, claims.sub);
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Cancels a schedule already handed off: the worldserver countdown is
+/// stopped with `.server shutdown cancel`, which covers restarts too.
+async fn cancel_started(state: &AppState, oid: ObjectId, id: &str, user: &str) -> axum::response::Response {
+    let schedule = match collection(state).find_one(doc! { "_id": oid }, None).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Schedule not found").into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
+    match schedule.status.as_str() {
+        "handing_off" => return (StatusCode::CONFLICT, "The restart is being handed off, try again in a moment").into_response(),
+        "started" if schedule.scheduled_at > now_secs() => {},
+        _ => return (StatusCode::CONFLICT, format!("Schedule is already {}", schedule.status)).into_response(),
+    }
+
+    if let Err(e) = state.soap.execute("server shutdown cancel").await {
+        tracing::warn!("Failed to cancel worldserver countdown: {}", e);
+        return e.into_response();
+    }
+    if collection(state).update_one(
+        doc! { "_id": oid, "status": "started" },
+        doc! { "$set": { "status": "cancelled" } },
+        None,
+    ).await.is_err() {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+    }
+    tracing::info!("Started server {} {} cancelled by {}", schedule.action, id, user);
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(reason: Option<&str>) -> ServerSchedule {
+        ServerSchedule {
+            id: None,
+            action: "restart".to_string(),
+            scheduled_at: 0,
+            duration_minutes: None,
+            reason: reason.map(str::to_string),
+            status: "pending".to_string(),
+            announced: Vec::new(),
+            error: None,
+            created_by: "admin".to_string(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn remaining_time_rounds_up_to_minutes_and_whole_hours() {
+        assert_eq!(format_remaining(3600), "1 hour");
+        assert_eq!(format_remaining(7200), "2 hours");
+        assert_eq!(format_remaining(1800), "30 minutes");
+        // A runner tick late still reads as the offset that was due.
+        assert_eq!(format_remaining(599), "10 minutes");
+        assert_eq!(format_remaining(3599), "1 hour");
+        assert_eq!(format_remaining(3660), "61 minutes");
+        assert_eq!(format_remaining(60), "1 minute");
+        assert_eq!(format_remaining(0), "1 minute");
+    }
+
+    #[test]
+    fn countdown_message_includes_the_reason() {
+        assert_eq!(countdown_message(&schedule(None), 900), "Server restart in 15 minutes");
+        assert_eq!(
+            countdown_message(&schedule(Some("Patch 2.1")), 3600),
+            "Server restart in 1 hour: Patch 2.1",
+        );
+    }
+
+    #[test]
+    fn due_offsets_skips_announced_and_future_ones() {
+        assert!(due_offsets(&[], 4000).is_empty());
+        assert_eq!(due_offsets(&[], 3600), vec![3600]);
+        assert_eq!(due_offsets(&[3600], 1700), vec![1800]);
+        assert!(due_offsets(&[3600, 1800], 1000).is_empty());
+        // Offsets missed while the backend was down all come due at once.
+        assert_eq!(due_offsets(&[3600], 250), vec![1800, 900, 600, 300]);
+    }
+}
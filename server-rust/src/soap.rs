@@ -0,0 +1,381 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::fmt;
+use std::time::Duration;
+
+use crate::{AppState, handlers::authenticate};
+
+const ENVELOPE_HEAD: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+xmlns:SOAP-ENC=\"http://schemas.xmlsoap.org/soap/encoding/\" \
+xmlns:xsi=\"http://www.w3.org/1999/XMLSchema-instance\" \
+xmlns:xsd=\"http://www.w3.org/1999/XMLSchema\" \
+xmlns:ns1=\"urn:AC\">\
+<SOAP-ENV:Body><ns1:executeCommand><command>";
+const ENVELOPE_TAIL: &str = "</command></ns1:executeCommand></SOAP-ENV:Body></SOAP-ENV:Envelope>";
+
+#[derive(Debug, PartialEq)]
+pub enum SoapError {
+    /// No SOAP account configured.
+    Disabled,
+    Unreachable(String),
+    Unauthorized,
+    /// The worldserver ran the command and reported a failure.
+    CommandFailed(String),
+    InvalidResponse,
+}
+
+impl fmt::Display for SoapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Disabled => write!(f, "SOAP is not configured"),
+            Self::Unreachable(e) => write!(f, "worldserver unreachable: {}", e),
+            Self::Unauthorized => write!(f, "SOAP credentials rejected"),
+            Self::CommandFailed(msg) => write!(f, "command failed: {}", msg),
+            Self::InvalidResponse => write!(f, "invalid SOAP response"),
+        }
+    }
+}
+
+impl IntoResponse for SoapError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Disabled => (StatusCode::SERVICE_UNAVAILABLE, "SOAP is not configured").into_response(),
+            Self::Unreachable(_) => (StatusCode::BAD_GATEWAY, "Worldserver unreachable").into_response(),
+            Self::Unauthorized => (StatusCode::BAD_GATEWAY, "SOAP credentials rejected").into_response(),
+            Self::CommandFailed(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg).into_response(),
+            Self::InvalidResponse => (StatusCode::BAD_GATEWAY, "Invalid response from worldserver").into_response(),
+        }
+    }
+}
+
+/// Runs GM console commands on the worldserver through its SOAP endpoint
+/// (`SOAP.Enabled` in worldserver.conf). The account needs GM level 3.
+pub struct SoapClient {
+    url: String,
+    username: String,
+    password: String,
+    http: reqwest::Client,
+}
+
+impl SoapClient {
+    pub fn new(host: &str, port: u16, username: &str, password: &str) -> Self {
+        SoapClient {
+            url: format!("http://{}:{}/", host, port),
+            username: username.to_string(),
+            password: password.to_string(),
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let host = std::env::var("SOAP_HOST").unwrap_or_else(|_| "localhost".to_string());
+        let port = std::env::var("SOAP_PORT").ok().and_then(|v| v.parse().ok()).unwrap_or(7878);
+        let username = std::env::var("SOAP_USER").unwrap_or_default();
+        let password = std::env::var("SOAP_PASS").unwrap_or_default();
+        SoapClient::new(&host, port, &username, &password)
+    }
+
+    pub fn is_configured(&self) -> bool {
+        !self.username.is_empty()
+    }
+
+    /// Executes a console command and returns its output text.
+    pub async fn execute(&self, command: &str) -> Result<String, SoapError> {
+        if !self.is_configured() {
+            return Err(SoapError::Disabled);
+        }
+
+        let body = format!("{}{}{}", ENVELOPE_HEAD, xml_escape(command), ENVELOPE_TAIL);
+        let resp = self.http.post(&self.url)
+            .basic_auth(&self.username, Some(&self.password))
+            .header("Content-Type", "text/xml; charset=utf-8")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| SoapError::Unreachable(e.to_string()))?;
+
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(SoapError::Unauthorized);
+        }
+        let text = resp.text().await.map_err(|e| SoapError::Unreachable(e.to_string()))?;
+
+        if let Some(result) = element_text(&text, "result") {
+            return Ok(result);
+        }
+        match element_text(&text, "faultstring") {
+            Some(fault) => Err(SoapError::CommandFailed(fault.trim().to_string())),
+            None => Err(SoapError::InvalidResponse),
+        }
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#xD;", "\r")
+        .replace("&#xA;", "\n")
+        .replace("&amp;", "&")
+}
+
+/// Unescaped text of the first `<tag>` element, ignoring namespace prefixes
+/// and attributes.
+fn element_text(xml: &str, tag: &str) -> Option<String> {
+    let mut search = 0;
+    while let Some(pos) = xml[search..].find('<') {
+        let start = search + pos + 1;
+        let end = start + xml[start..].find('>')?;
+        let name = xml[start..end].split_whitespace().next().unwrap_or("");
+        let local = name.rsplit(':').next().unwrap_or(name);
+        if local == tag && !xml[start..end].ends_with('/') {
+            let content_start = end + 1;
+            let close = format!("</{}>", name);
+            let content_end = content_start + xml[content_start..].find(&close)?;
+            return Some(xml_unescape(&xml[content_start..content_end]));
+        }
+        search = end;
+    }
+    None
+}
+
+/// Non-empty output lines.
+pub fn output_lines(output: &str) -> Vec<String> {
+    output.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect()
+}
+
+/// Fields of interest from `server info`.
+#[derive(Debug, Default, PartialEq)]
+pub struct ServerInfo {
+    pub revision: Option<String>,
+    pub connected_players: Option<u32>,
+    pub characters_in_world: Option<u32>,
+    pub connection_peak: Option<u32>,
+    pub uptime: Option<String>,
+}
+
+fn leading_number(text: &str) -> Option<u32> {
+    let digits: String = text.trim().chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+pub fn parse_server_info(output: &str) -> ServerInfo {
+    let mut info = ServerInfo::default();
+    for line in output_lines(output) {
+        if line.starts_with("AzerothCore") {
+            info.revision = Some(line.clone());
+        }
+        if let Some(rest) = line.strip_prefix("Connected players:") {
+            info.connected_players = leading_number(rest);
+            if let Some((_, chars)) = rest.split_once("Characters in world:") {
+                info.characters_in_world = leading_number(chars);
+            }
+        } else if let Some(rest) = line.strip_prefix("Connection peak:") {
+            info.connection_peak = leading_number(rest);
+        } else if let Some(rest) = line.strip_prefix("Server uptime:") {
+            info.uptime = Some(rest.trim().trim_end_matches('.').to_string());
+        }
+    }
+    info
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SoapCommandRequest {
+    pub command: String,
+}
+
+pub async fn run_command(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SoapCommandRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    // The console accepts commands with or without the leading dot.
+    let command = payload.command.trim().trim_start_matches('.').to_string();
+    if command.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Command is required").into_response();
+    }
+
+    tracing::info!("Admin {} running worldserver command: {}", claims.sub, command);
+    match state.soap.execute(&command).await {
+        Ok(output) => Json(serde_json::json!({
+            "command": command,
+            "output": output,
+            "lines": output_lines(&output),
+        })).into_response(),
+        Err(e) => {
+            tracing::warn!("Worldserver command '{}' failed: {}", command, e);
+            e.into_response()
+        }
+    }
+}
+
+pub async fn get_server_info(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if claims.role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    match state.soap.execute("server info").await {
+        Ok(output) => {
+            let info = parse_server_info(&output);
+            Json(serde_json::json!({
+                "revision": info.revision,
+                "connectedPlayers": info.connected_players,
+                "charactersInWorld": info.characters_in_world,
+                "connectionPeak": info.connection_peak,
+                "uptime": info.uptime,
+                "lines": output_lines(&output),
+            })).into_response()
+        },
+        Err(e) => {
+            tracing::warn!("Failed to fetch worldserver info: {}", e);
+            e.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, routing::post, Router};
+
+    const SERVER_INFO: &str = "AzerothCore rev. 1a2b3c4 2024-01-01 (Unix, RelWithDebInfo, Static)\r\n\
+Connected players: 12. Characters in world: 14.\r\n\
+Connection peak: 40.\r\n\
+Server uptime: 2 hour(s) 5 minute(s) 1 second(s)\r\n\
+Update time diff: 12ms. Last 500 diffs summary:\r\n";
+
+    fn success(result: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://schemas.xmlsoap.org/soap/envelope/\" xmlns:ns1=\"urn:AC\">\
+<SOAP-ENV:Body><ns1:executeCommandResponse><result>{}</result></ns1:executeCommandResponse></SOAP-ENV:Body></SOAP-ENV:Envelope>",
+            xml_escape(result)
+        )
+    }
+
+    fn fault(message: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://schemas.xmlsoap.org/soap/envelope/\">\
+<SOAP-ENV:Body><SOAP-ENV:Fault><faultcode>SOAP-ENV:Client</faultcode><faultstring>{}</faultstring></SOAP-ENV:Fault></SOAP-ENV:Body></SOAP-ENV:Envelope>",
+            xml_escape(message)
+        )
+    }
+
+    /// Behaves like the worldserver's SOAP listener for the `admin:secret` account.
+    async fn mock_soap(headers: HeaderMap, body: Bytes) -> Response {
+        // base64("admin:secret")
+        if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some("Basic YWRtaW46c2VjcmV0") {
+            return (StatusCode::UNAUTHORIZED, "401 Authorization Required").into_response();
+        }
+        let body = String::from_utf8_lossy(&body);
+        let command = element_text(&body, "command").unwrap_or_default();
+        match command.as_str() {
+            "server info" => success(SERVER_INFO).into_response(),
+            "bogus" => (StatusCode::INTERNAL_SERVER_ERROR, fault("There is no such command.")).into_response(),
+            other => success(&format!("echo: {}", other)).into_response(),
+        }
+    }
+
+    async fn spawn_mock() -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/", post(mock_soap))).await.unwrap();
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn executes_command_and_returns_output() {
+        let port = spawn_mock().await;
+        let client = SoapClient::new("127.0.0.1", port, "admin", "secret");
+
+        let output = client.execute("server info").await.unwrap();
+        assert_eq!(output, SERVER_INFO);
+    }
+
+    #[tokio::test]
+    async fn escapes_command_text() {
+        let port = spawn_mock().await;
+        let client = SoapClient::new("127.0.0.1", port, "admin", "secret");
+
+        let output = client.execute("announce <Raid> & \"friends\"").await.unwrap();
+        assert_eq!(output, "echo: announce <Raid> & \"friends\"");
+    }
+
+    #[tokio::test]
+    async fn reports_command_faults() {
+        let port = spawn_mock().await;
+        let client = SoapClient::new("127.0.0.1", port, "admin", "secret");
+
+        let err = client.execute("bogus").await.unwrap_err();
+        assert_eq!(err, SoapError::CommandFailed("There is no such command.".to_string()));
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_credentials() {
+        let port = spawn_mock().await;
+        let client = SoapClient::new("127.0.0.1", port, "admin", "wrong");
+
+        assert_eq!(client.execute("server info").await.unwrap_err(), SoapError::Unauthorized);
+    }
+
+    #[tokio::test]
+    async fn reports_unreachable_server() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let client = SoapClient::new("127.0.0.1", port, "admin", "secret");
+
+        assert!(matches!(client.execute("server info").await, Err(SoapError::Unreachable(_))));
+    }
+
+    #[tokio::test]
+    async fn refuses_without_credentials() {
+        let client = SoapClient::new("127.0.0.1", 7878, "", "");
+        assert_eq!(client.execute("server info").await.unwrap_err(), SoapError::Disabled);
+    }
+
+    #[test]
+    fn parses_server_info() {
+        let info = parse_server_info(SERVER_INFO);
+        assert_eq!(info.connected_players, Some(12));
+        assert_eq!(info.characters_in_world, Some(14));
+        assert_eq!(info.connection_peak, Some(40));
+        assert_eq!(info.uptime.as_deref(), Some("2 hour(s) 5 minute(s) 1 second(s)"));
+        assert!(info.revision.unwrap().starts_with("AzerothCore rev."));
+    }
+}
@@ -0,0 +1,214 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use mongodb::bson::doc;
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::{AppState, clock::now_secs, models::{RealmStatus, RealmUptime, ServiceProbe}, population::worldserver_up};
+
+/// Last realm status and when it was taken. Probing opens sockets and hits
+/// every database, so `/api/status` serves this for `STATUS_CACHE_SECS`.
+#[derive(Default)]
+pub struct StatusCache {
+    entry: Mutex<Option<(Instant, RealmStatus)>>,
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+fn probe_timeout() -> Duration {
+    Duration::from_millis(env_or("STATUS_PROBE_TIMEOUT_MS", 1500))
+}
+
+fn probe_result<E: std::fmt::Display>(started: Instant, result: Result<(), E>) -> ServiceProbe {
+    match result {
+        Ok(()) => ServiceProbe {
+            online: true,
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            error: None,
+        },
+        Err(e) => ServiceProbe {
+            online: false,
+            latency_ms: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+async fn probe_tcp(host: &str, port: u16) -> ServiceProbe {
+    let started = Instant::now();
+    let result = match tokio::time::timeout(probe_timeout(), tokio::net::TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("timed out".to_string()),
+    };
+    probe_result(started, result)
+}
+
+async fn probe_mysql(pool: &sqlx::MySqlPool) -> ServiceProbe {
+    let started = Instant::now();
+    let result = match tokio::time::timeout(probe_timeout(), sqlx::query("SELECT 1").execute(pool)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("timed out".to_string()),
+    };
+    probe_result(started, result)
+}
+
+async fn probe_mongo(db: &mongodb::Database) -> ServiceProbe {
+    let started = Instant::now();
+    let result = match tokio::time::timeout(probe_timeout(), db.run_command(doc! { "ping": 1 }, None)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("timed out".to_string()),
+    };
+    probe_result(started, result)
+}
+
+async fn probe_services(state: &AppState) -> HashMap<String, ServiceProbe> {
+    let (mongo, auth, characters, world) = tokio::join!(
+        probe_mongo(&state.mongo),
+        probe_mysql(&state.mysql_auth),
+        probe_mysql(&state.mysql_char),
+        probe_mysql(&state.mysql_world),
+    );
+    HashMap::from([
+        ("mongo".to_string(), mongo),
+        ("mysqlAuth".to_string(), auth),
+        ("mysqlCharacters".to_string(), characters),
+        ("mysqlWorld".to_string(), world),
+    ])
+}
+
+/// Latest row the worldserver wrote to the auth `uptime` table.
+async fn realm_uptime(pool: &sqlx::MySqlPool, realm_id: u32) -> Result<Option<RealmUptime>, sqlx::Error> {
+    let row = sqlx::query("SELECT starttime, uptime, maxplayers, revision FROM uptime WHERE realmid = ? ORDER BY starttime DESC LIMIT 1")
+        .bind(realm_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.map(|row| RealmUptime {
+        started_at: row.try_get::<u32, _>("starttime").unwrap_or_default() as i64,
+        uptime: row.try_get::<u32, _>("uptime").unwrap_or_default(),
+        max_players: row.try_get::<u16, _>("maxplayers").unwrap_or_default(),
+        revision: row.try_get::<String, _>("revision").unwrap_or_default(),
+    }))
+}
+
+async fn probe(state: &AppState) -> RealmStatus {
+    let auth_host = std::env::var("AUTHSERVER_HOST").unwrap_or_else(|_| "localhost".to_string());
+    let world_host = std::env::var("WORLDSERVER_HOST").unwrap_or_else(|_| "localhost".to_string());
+    let realm_id = state.realms.default_id();
+
+    let (authserver, worldserver, services) = tokio::join!(
+        probe_tcp(&auth_host, env_or("AUTHSERVER_PORT", 3724)),
+        probe_tcp(&world_host, env_or("WORLDSERVER_PORT", 8085)),
+        probe_services(state),
+    );
+
+    let uptime = match realm_uptime(&state.mysql_auth, realm_id).await {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::warn!("Failed to read realm uptime: {}", e);
+            None
+        }
+    };
+
+    let realm_players = online_per_realm(state, worldserver.online).await;
+    let online_players = (!realm_players.is_empty()).then(|| realm_players.values().sum());
+
+    let status = match (authserver.online, worldserver.online) {
+        (true, true) if services.values().all(|s| s.online) => "online",
+        (false, false) => "offline",
+        _ => "degraded",
+    };
+
+    RealmStatus {
+        status: status.to_string(),
+        authserver,
+        worldserver,
+        uptime,
+        online_players,
+        realm_players,
+        services,
+        checked_at: now_secs(),
+        maintenance: Vec::new(),
+    }
+}
+
+/// Online players on every realm. The characters table keeps `online = 1`
+/// for whoever was logged in when a worldserver went down, so a realm counts
+/// as empty unless its worldserver is up: the probed one for the default
+/// realm, a recent `uptime` report for the others.
+async fn online_per_realm(state: &AppState, default_up: bool) -> BTreeMap<u32, u32> {
+    let now = now_secs();
+    let mut players = BTreeMap::new();
+    for (realm, pool) in state.realms.all() {
+        let up = if realm == state.realms.default_id() {
+            Ok(default_up)
+        } else {
+            worldserver_up(state, realm, now).await
+        };
+        let count = match up {
+            Ok(false) => Ok(0),
+            Ok(true) => sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM characters WHERE online = 1")
+                .fetch_one(&pool)
+                .await,
+            Err(e) => Err(e),
+        };
+        match count {
+            Ok(count) => {
+                players.insert(realm, count as u32);
+            },
+            Err(e) => tracing::warn!("Failed to count online players on realm {}: {}", realm, e),
+        }
+    }
+    players
+}
+
+/// Cached realm status, re-probed once older than `STATUS_CACHE_SECS`.
+pub async fn current_status(state: &AppState) -> RealmStatus {
+    let ttl = Duration::from_secs(env_or("STATUS_CACHE_SECS", 15));
+    let mut entry = state.status.entry.lock().await;
+    if let Some((at, status)) = entry.as_ref() {
+        if at.elapsed() < ttl {
+            return status.clone();
+        }
+    }
+
+    let status = probe(state).await;
+    *entry = Some((Instant::now(), status.clone()));
+    status
+}
+
+pub async fn get_status(State(state): State<AppState>) -> impl IntoResponse {
+    // Schedules change independently of the probes, so they skip the cache.
+    let mut status = current_status(&state).await;
+    status.maintenance = crate::schedules::upcoming(&state).await;
+    Json(status)
+}
+
+/// Liveness: the process is up and serving requests.
+pub async fn liveness() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok", "backend": "rust" }))
+}
+
+/// Readiness: every backing database answers. Not cached, so orchestrators
+/// see recovery as soon as it happens.
+pub async fn readiness(State(state): State<AppState>) -> impl IntoResponse {
+    let services = probe_services(&state).await;
+    let ready = services.values().all(|s| s.online);
+    let code = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (code, Json(serde_json::json!({
+        "status": if ready { "ready" } else { "unavailable" },
+        "services": services,
+    }))).into_response()
+}
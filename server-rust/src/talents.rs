@@ -0,0 +1,292 @@
+use serde::Deserialize;
+use sqlx::Row;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::{
+    dbc::DbcFile,
+    models::{CharacterGlyph, CharacterTalentSpec, TalentTreeSummary},
+};
+
+/// Field counts and indexes of the 3.3.5a tables read by `from_dbc`.
+const TALENT_FIELDS: usize = 23;
+const TALENT_TAB_FIELDS: usize = 24;
+const GLYPH_PROPERTIES_FIELDS: usize = 4;
+const SPELL_FIELDS: usize = 234;
+/// enUS `Name_lang` in TalentTab.dbc and `SpellName` in Spell.dbc.
+const TALENT_TAB_NAME: usize = 1;
+const SPELL_NAME: usize = 136;
+
+/// One talent, with the spell id taught by each rank in order. The data file
+/// also carries the Talent.dbc `id` for reference.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TalentMeta {
+    pub ranks: Vec<u32>,
+}
+
+/// A talent tree, talents listed in calculator order (row by row, left to right).
+#[derive(Debug, Clone, Deserialize)]
+pub struct TalentTree {
+    pub name: String,
+    pub talents: Vec<TalentMeta>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClassTalents {
+    pub class: u8,
+    pub trees: Vec<TalentTree>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GlyphMeta {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TalentData {
+    #[serde(default)]
+    classes: Vec<ClassTalents>,
+    #[serde(default)]
+    glyphs: Vec<GlyphMeta>,
+}
+
+/// Talent tree layout and glyph names. `character_talent` only stores the
+/// learned spell, so tree placement and ranks have to come from here.
+#[derive(Debug, Default)]
+pub struct TalentCatalog {
+    classes: HashMap<u8, Vec<TalentTree>>,
+    /// spell id -> (class, tree index, talent index, rank)
+    spells: HashMap<u32, (u8, usize, usize, u8)>,
+    glyphs: HashMap<u32, String>,
+}
+
+impl TalentCatalog {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let data: TalentData = serde_json::from_str(&raw).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Self::from_data(data))
+    }
+
+    /// Builds the catalog from the client's Talent, TalentTab, GlyphProperties
+    /// and Spell tables, which cover every class.
+    pub fn from_dbc(dir: &Path) -> Result<Self, String> {
+        // (class, order) -> (tab id, name); pet talent tabs have no class.
+        let mut tabs: BTreeMap<(u8, u32), (u32, String)> = BTreeMap::new();
+        for record in DbcFile::open(dir, "TalentTab.dbc", TALENT_TAB_FIELDS)?.records() {
+            let class_mask = record.u32(20);
+            if class_mask == 0 {
+                continue;
+            }
+            let class = class_mask.trailing_zeros() as u8 + 1;
+            tabs.insert((class, record.u32(22)), (record.u32(0), record.string(TALENT_TAB_NAME).unwrap_or_default()));
+        }
+
+        // tab id -> (tier, column) -> spell per rank
+        let mut talents: HashMap<u32, BTreeMap<(u32, u32), Vec<u32>>> = HashMap::new();
+        for record in DbcFile::open(dir, "Talent.dbc", TALENT_FIELDS)?.records() {
+            let ranks: Vec<u32> = (4..13).map(|f| record.u32(f)).take_while(|spell| *spell != 0).collect();
+            talents.entry(record.u32(1)).or_default().insert((record.u32(2), record.u32(3)), ranks);
+        }
+
+        let mut classes: BTreeMap<u8, Vec<TalentTree>> = BTreeMap::new();
+        for ((class, _), (tab, name)) in tabs {
+            let talents = talents.remove(&tab).unwrap_or_default()
+                .into_values()
+                .map(|ranks| TalentMeta { ranks })
+                .collect();
+            classes.entry(class).or_default().push(TalentTree { name, talents });
+        }
+
+        let glyph_spells: Vec<(u32, u32)> = DbcFile::open(dir, "GlyphProperties.dbc", GLYPH_PROPERTIES_FIELDS)?
+            .records()
+            .map(|record| (record.u32(0), record.u32(1)))
+            .collect();
+        let wanted: HashSet<u32> = glyph_spells.iter().map(|(_, spell)| *spell).collect();
+        let spell_names: HashMap<u32, String> = DbcFile::open(dir, "Spell.dbc", SPELL_FIELDS)?
+            .records()
+            .filter(|record| wanted.contains(&record.u32(0)))
+            .filter_map(|record| Some((record.u32(0), record.string(SPELL_NAME)?)))
+            .collect();
+        let glyphs = glyph_spells.into_iter()
+            .filter_map(|(id, spell)| Some(GlyphMeta { id, name: spell_names.get(&spell)?.clone() }))
+            .collect();
+
+        Ok(Self::from_data(TalentData {
+            classes: classes.into_iter().map(|(class, trees)| ClassTalents { class, trees }).collect(),
+            glyphs,
+        }))
+    }
+
+    fn from_data(data: TalentData) -> Self {
+        let mut catalog = TalentCatalog::default();
+        for class in data.classes {
+            for (tree_index, tree) in class.trees.iter().enumerate() {
+                for (talent_index, talent) in tree.talents.iter().enumerate() {
+                    for (rank, spell) in talent.ranks.iter().enumerate() {
+                        catalog.spells.insert(*spell, (class.class, tree_index, talent_index, rank as u8 + 1));
+                    }
+                }
+            }
+            catalog.classes.insert(class.class, class.trees);
+        }
+        catalog.glyphs = data.glyphs.into_iter().map(|g| (g.id, g.name)).collect();
+        catalog
+    }
+
+    pub fn len(&self) -> usize {
+        self.classes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    pub fn glyph_name(&self, id: u32) -> Option<&str> {
+        self.glyphs.get(&id).map(String::as_str)
+    }
+
+    /// Builds the tree summary and export string for a set of learned spells.
+    /// Returns None when the class has no talent data, so no partial or
+    /// misplaced export string is ever produced.
+    fn summarize(&self, class: u8, spells: &[u32]) -> Option<(Vec<TalentTreeSummary>, String)> {
+        let trees = self.classes.get(&class)?;
+        let mut ranks: Vec<Vec<u8>> = trees.iter().map(|t| vec![0; t.talents.len()]).collect();
+
+        for spell in spells {
+            if let Some((c, tree, talent, rank)) = self.spells.get(spell) {
+                if *c == class {
+                    let slot = &mut ranks[*tree][*talent];
+                    *slot = (*slot).max(*rank);
+                }
+            }
+        }
+
+        let summary = trees.iter().zip(&ranks).map(|(tree, r)| TalentTreeSummary {
+            name: tree.name.clone(),
+            points: r.iter().map(|v| *v as u32).sum(),
+        }).collect();
+
+        let export = ranks.iter().map(|r| {
+            let digits: String = r.iter().map(|v| char::from(b'0' + *v)).collect();
+            digits.trim_end_matches('0').to_string()
+        }).collect::<Vec<_>>().join("-");
+
+        Some((summary, export.trim_end_matches('-').to_string()))
+    }
+}
+
+/// Talent specs (dual spec included) with glyphs for a character.
+pub async fn character_talents(
+    pool: &sqlx::MySqlPool,
+    catalog: &TalentCatalog,
+    guid: u32,
+    class: u8,
+    active_group: u8,
+) -> Result<Vec<CharacterTalentSpec>, sqlx::Error> {
+    let talent_rows = sqlx::query("SELECT spell, specMask FROM character_talent WHERE guid = ?")
+        .bind(guid)
+        .fetch_all(pool)
+        .await?;
+
+    let glyph_rows = sqlx::query(
+        "SELECT talentGroup, glyph1, glyph2, glyph3, glyph4, glyph5, glyph6 FROM character_glyphs WHERE guid = ? ORDER BY talentGroup",
+    )
+    .bind(guid)
+    .fetch_all(pool)
+    .await?;
+
+    let mut specs = Vec::new();
+    for group in 0..2u8 {
+        let spells: Vec<u32> = talent_rows.iter()
+            .filter(|row| row.try_get::<u8, _>("specMask").unwrap_or_default() & (1 << group) != 0)
+            .map(|row| row.try_get::<u32, _>("spell").unwrap_or_default())
+            .collect();
+
+        let glyph_row = glyph_rows.iter().find(|row| row.try_get::<u8, _>("talentGroup").unwrap_or_default() == group);
+        let glyphs: Vec<CharacterGlyph> = match glyph_row {
+            Some(row) => (1..=6u8).filter_map(|slot| {
+                let id = row.try_get::<u16, _>(format!("glyph{}", slot).as_str()).unwrap_or_default() as u32;
+                (id != 0).then(|| CharacterGlyph {
+                    slot,
+                    id,
+                    name: catalog.glyph_name(id).map(str::to_string),
+                })
+            }).collect(),
+            None => Vec::new(),
+        };
+
+        // The second spec only exists once dual spec has been learned.
+        if group > 0 && spells.is_empty() && glyphs.is_empty() {
+            continue;
+        }
+
+        let (trees, export_string) = match catalog.summarize(class, &spells) {
+            Some((trees, export)) => (trees, Some(export)),
+            None => (Vec::new(), None),
+        };
+        specs.push(CharacterTalentSpec {
+            group,
+            active: group == active_group,
+            trees,
+            export_string,
+            glyphs,
+        });
+    }
+    Ok(specs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(name: &str, talents: &[&[u32]]) -> TalentTree {
+        TalentTree {
+            name: name.to_string(),
+            talents: talents.iter().map(|ranks| TalentMeta { ranks: ranks.to_vec() }).collect(),
+        }
+    }
+
+    fn catalog() -> TalentCatalog {
+        TalentCatalog::from_data(TalentData {
+            classes: vec![
+                ClassTalents {
+                    class: 1,
+                    trees: vec![
+                        tree("Arms", &[&[100, 101, 102], &[110]]),
+                        tree("Fury", &[&[200, 201]]),
+                        tree("Protection", &[&[300]]),
+                    ],
+                },
+                ClassTalents { class: 2, trees: vec![tree("Holy", &[&[900]])] },
+            ],
+            glyphs: Vec::new(),
+        })
+    }
+
+    fn points(summary: &[TalentTreeSummary]) -> Vec<(&str, u32)> {
+        summary.iter().map(|t| (t.name.as_str(), t.points)).collect()
+    }
+
+    #[test]
+    fn keeps_the_highest_rank_and_ignores_other_classes() {
+        let (summary, export) = catalog().summarize(1, &[101, 100, 200, 900, 555]).unwrap();
+
+        assert_eq!(points(&summary), vec![("Arms", 2), ("Fury", 1), ("Protection", 0)]);
+        assert_eq!(export, "2-1");
+    }
+
+    #[test]
+    fn export_keeps_the_position_of_trailing_trees() {
+        let catalog = catalog();
+
+        assert_eq!(catalog.summarize(1, &[300]).unwrap().1, "--1");
+        assert_eq!(catalog.summarize(1, &[110]).unwrap().1, "01");
+        assert_eq!(catalog.summarize(1, &[]).unwrap().1, "");
+    }
+
+    #[test]
+    fn class_without_data_has_no_summary() {
+        assert!(catalog().summarize(5, &[100]).is_none());
+    }
+}
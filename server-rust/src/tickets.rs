@@ -0,0 +1,508 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use mongodb::{
+    bson::{doc, Document},
+    Collection,
+};
+use serde::Deserialize;
+use sqlx::{MySql, QueryBuilder, Row};
+
+use crate::{
+    AppState,
+    clock::{interval_from_env, now_secs},
+    handlers::{authenticate, caller_game_accounts, is_duplicate_key, send_email},
+    items::like_pattern,
+    models::GmTicket,
+    realms::RealmDb,
+    retention,
+};
+
+/// gm_ticket.type values.
+const TICKET_OPEN: u8 = 0;
+const TICKET_CLOSED: u8 = 1;
+const TICKET_CHARACTER_DELETED: u8 = 2;
+
+const MY_TICKETS_LIMIT: u32 = 100;
+
+/// How far back the notifier looks for completed tickets. The worldserver
+/// saves tickets some time after `lastModifiedTime`, so this spans many polls.
+const NOTIFY_LOOKBACK_SECS: i64 = 24 * 3600;
+/// Sent notifications are remembered well past the lookback.
+const NOTIFICATION_RETENTION_DAYS: i64 = 90;
+
+const TICKET_COLUMNS: &str =
+    "SELECT t.id, t.type, t.playerGuid, t.name, t.description, t.createTime, t.lastModifiedTime, t.closedBy, \
+     t.assignedTo, t.comment, t.response, t.completed, t.escalated, t.mapId, gm.name AS assignedName \
+     FROM gm_ticket t LEFT JOIN characters gm ON gm.guid = t.assignedTo AND t.assignedTo <> 0";
+
+fn ticket_status(kind: u8, closed_by: i32, completed: bool, assigned: bool) -> &'static str {
+    match kind {
+        TICKET_CHARACTER_DELETED => "deleted",
+        TICKET_CLOSED => "closed",
+        _ if closed_by != 0 => "closed",
+        _ if completed => "answered",
+        _ if assigned => "assigned",
+        _ => "open",
+    }
+}
+
+fn non_empty(text: String) -> Option<String> {
+    Some(text).filter(|t| !t.trim().is_empty())
+}
+
+fn ticket_from_row(row: &sqlx::mysql::MySqlRow, realm: u32, staff: bool) -> GmTicket {
+    let assigned_name = row.try_get::<Option<String>, _>("assignedName").unwrap_or_default();
+    let completed = row.try_get::<u8, _>("completed").unwrap_or_default() != 0;
+    let status = ticket_status(
+        row.try_get::<u8, _>("type").unwrap_or_default(),
+        row.try_get::<i32, _>("closedBy").unwrap_or_default(),
+        completed,
+        row.try_get::<u32, _>("assignedTo").unwrap_or_default() != 0,
+    );
+
+    GmTicket {
+        id: row.try_get::<u32, _>("id").unwrap_or_default(),
+        realm,
+        character: row.try_get::<String, _>("name").unwrap_or_default(),
+        player_guid: row.try_get::<u32, _>("playerGuid").unwrap_or_default(),
+        status: status.to_string(),
+        description: row.try_get::<String, _>("description").unwrap_or_default(),
+        created_at: row.try_get::<u32, _>("createTime").unwrap_or_default() as i64,
+        updated_at: row.try_get::<u32, _>("lastModifiedTime").unwrap_or_default() as i64,
+        // Players only see the answer once a GM has marked it complete.
+        response: non_empty(row.try_get::<String, _>("response").unwrap_or_default()).filter(|_| staff || completed),
+        assigned_to: assigned_name.filter(|_| staff),
+        comment: non_empty(row.try_get::<String, _>("comment").unwrap_or_default()).filter(|_| staff),
+        escalated: staff.then(|| row.try_get::<u8, _>("escalated").unwrap_or_default() != 0),
+        map_id: staff.then(|| row.try_get::<u16, _>("mapId").unwrap_or_default()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TicketQuery {
+    /// open (default, anything not closed), closed or all.
+    pub status: Option<String>,
+    /// GM character name, or "none" for unassigned tickets.
+    pub assigned: Option<String>,
+    pub escalated: Option<bool>,
+    pub character: Option<String>,
+    pub search: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssignTicketRequest {
+    /// GM character to assign; empty or missing unassigns.
+    pub gm: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TicketTextRequest {
+    pub text: String,
+}
+
+/// Ticket changes go through the worldserver, which keeps open tickets in
+/// memory and would overwrite direct database edits on its next save. The
+/// GM tools therefore act on the realm SOAP is connected to, the default one;
+/// tickets of the other realms can be listed and read but not changed.
+async fn run_ticket_command(state: &AppState, command: String) -> Result<(), axum::response::Response> {
+    state.soap.execute(&command).await.map(|_| ()).map_err(|e| {
+        tracing::warn!("Ticket command '{}' failed: {}", command, e);
+        e.into_response()
+    })
+}
+
+async fn load_ticket(pool: &sqlx::MySqlPool, realm: u32, id: u32, staff: bool) -> Result<Option<GmTicket>, sqlx::Error> {
+    let row = sqlx::query(&format!("{} WHERE t.id = ?", TICKET_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| ticket_from_row(&r, realm, staff)))
+}
+
+fn validate_text(text: &str) -> Result<&str, &'static str> {
+    let text = text.trim();
+    if text.is_empty() || text.len() > 500 || text.contains(['\r', '\n']) {
+        return Err("Text must be a single line of 1-500 characters");
+    }
+    Ok(text)
+}
+
+pub async fn list_tickets(
+    realm: RealmDb,
+    headers: HeaderMap,
+    Query(params): Query<TicketQuery>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if !claims.is_staff() {
+        return (StatusCode::FORBIDDEN, "Staff access required").into_response();
+    }
+
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+    let offset = params.offset.unwrap_or(0);
+
+    let mut builder: QueryBuilder<MySql> = QueryBuilder::new(TICKET_COLUMNS);
+    builder.push(" WHERE 1 = 1");
+    match params.status.as_deref().unwrap_or("open") {
+        "open" => { builder.push(" AND t.type = ").push_bind(TICKET_OPEN).push(" AND t.closedBy = 0"); },
+        "closed" => { builder.push(" AND (t.type <> ").push_bind(TICKET_OPEN).push(" OR t.closedBy <> 0)"); },
+        "all" => {},
+        _ => return (StatusCode::BAD_REQUEST, "Status must be open, closed or all").into_response(),
+    }
+    match params.assigned.as_deref().map(str::trim) {
+        Some("none") => { builder.push(" AND t.assignedTo = 0"); },
+        Some(gm) if !gm.is_empty() => { builder.push(" AND gm.name = ").push_bind(gm.to_string()); },
+        _ => {},
+    }
+    if let Some(escalated) = params.escalated {
+        builder.push(if escalated { " AND t.escalated <> 0" } else { " AND t.escalated = 0" });
+    }
+    if let Some(character) = params.character.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        builder.push(" AND t.name = ").push_bind(character.to_string());
+    }
+    if let Some(search) = params.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        builder.push(" AND t.description LIKE ").push_bind(like_pattern(search));
+    }
+    builder.push(" ORDER BY t.escalated DESC, t.createTime ASC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(offset);
+
+    match builder.build().fetch_all(&realm.pool).await {
+        Ok(rows) => Json(rows.iter().map(|r| ticket_from_row(r, realm.id, true)).collect::<Vec<_>>()).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to list tickets: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+pub async fn get_ticket(
+    realm: RealmDb,
+    headers: HeaderMap,
+    Path(id): Path<u32>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if !claims.is_staff() {
+        return (StatusCode::FORBIDDEN, "Staff access required").into_response();
+    }
+
+    match load_ticket(&realm.pool, realm.id, id, true).await {
+        Ok(Some(ticket)) => Json(ticket).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Ticket not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load ticket {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+pub async fn assign_ticket(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u32>,
+    Json(payload): Json<AssignTicketRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if !claims.is_staff() {
+        return (StatusCode::FORBIDDEN, "Staff access required").into_response();
+    }
+
+    let command = match payload.gm.as_deref().map(str::trim).filter(|g| !g.is_empty()) {
+        Some(gm) if gm.len() > 12 || !gm.chars().all(|c| c.is_ascii_alphabetic()) => {
+            return (StatusCode::BAD_REQUEST, "GM must be a character name").into_response();
+        },
+        Some(gm) => format!("ticket assign {} {}", id, gm),
+        None => format!("ticket unassign {}", id),
+    };
+    if let Err(response) = run_ticket_command(&state, command).await {
+        return response;
+    }
+    tracing::info!("Ticket {} assignment changed by {}", id, claims.sub);
+
+    ticket_response(&state, id).await
+}
+
+pub async fn comment_ticket(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u32>,
+    Json(payload): Json<TicketTextRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if !claims.is_staff() {
+        return (StatusCode::FORBIDDEN, "Staff access required").into_response();
+    }
+    let text = match validate_text(&payload.text) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    if let Err(response) = run_ticket_command(&state, format!("ticket comment {} {}", id, text)).await {
+        return response;
+    }
+    tracing::info!("Ticket {} commented by {}", id, claims.sub);
+
+    ticket_response(&state, id).await
+}
+
+/// Sends the answer to the player in-game, marks the ticket complete and
+/// emails the account owner.
+pub async fn answer_ticket(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u32>,
+    Json(payload): Json<TicketTextRequest>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if !claims.is_staff() {
+        return (StatusCode::FORBIDDEN, "Staff access required").into_response();
+    }
+    let text = match validate_text(&payload.text) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    if let Err(response) = run_ticket_command(&state, format!("ticket response append {} {}", id, text)).await {
+        return response;
+    }
+    if let Err(response) = run_ticket_command(&state, format!("ticket complete {}", id)).await {
+        return response;
+    }
+    tracing::info!("Ticket {} answered by {}", id, claims.sub);
+
+    match claim_notification(&state, state.realms.default_id(), id).await {
+        Ok(false) => {},
+        Ok(true) => notify_player(&state, &state.mysql_char, id, text).await,
+        Err(e) => {
+            tracing::warn!("Failed to record notification of ticket {}: {}", id, e);
+            notify_player(&state, &state.mysql_char, id, text).await;
+        }
+    }
+    ticket_response(&state, id).await
+}
+
+pub async fn close_ticket(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<u32>,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    if !claims.is_staff() {
+        return (StatusCode::FORBIDDEN, "Staff access required").into_response();
+    }
+
+    if let Err(response) = run_ticket_command(&state, format!("ticket close {}", id)).await {
+        return response;
+    }
+    tracing::info!("Ticket {} closed by {}", id, claims.sub);
+
+    ticket_response(&state, id).await
+}
+
+/// The ticket after a change. The worldserver may not have saved it yet, so
+/// fields can lag behind until its next ticket save.
+async fn ticket_response(state: &AppState, id: u32) -> axum::response::Response {
+    match load_ticket(&state.mysql_char, state.realms.default_id(), id, true).await {
+        Ok(Some(ticket)) => Json(ticket).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Ticket not found").into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load ticket {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+fn notifications(state: &AppState) -> Collection<Document> {
+    state.mongo.collection("ticket_notifications")
+}
+
+/// Claims the answer email of a ticket; false when it was already sent.
+async fn claim_notification(state: &AppState, realm: u32, id: u32) -> Result<bool, mongodb::error::Error> {
+    let now = now_secs();
+    let mut claim = doc! { "_id": format!("{}:{}", realm, id), "realm": realm, "ticketId": id, "at": now };
+    if let Some(expires_at) = retention::expires_at(now, NOTIFICATION_RETENTION_DAYS) {
+        claim.insert("expiresAt", expires_at);
+    }
+    match notifications(state).insert_one(claim, None).await {
+        Ok(_) => Ok(true),
+        Err(e) if is_duplicate_key(&e) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Starts the background task that emails players when a GM completes their
+/// ticket in-game. Dashboard answers claim the same notification, so each
+/// ticket is emailed once. `TICKET_NOTIFY_INTERVAL_SECS=0` disables it.
+pub fn spawn_notifier(state: AppState) {
+    let Some(period) = interval_from_env("TICKET_NOTIFY_INTERVAL_SECS", 60) else {
+        tracing::info!("Ticket notifier disabled");
+        return;
+    };
+
+    tokio::spawn(async move {
+        if let Err(e) = retention::ensure_ttl(&notifications(&state), NOTIFICATION_RETENTION_DAYS).await {
+            tracing::warn!("Failed to set up ticket_notifications retention: {}", e);
+        }
+        // On the first run, tickets completed before the notifier existed
+        // are not emailed.
+        let since = match notifications(&state).count_documents(doc! {}, None).await {
+            Ok(0) => now_secs(),
+            Ok(_) => 0,
+            Err(e) => {
+                tracing::warn!("Ticket notifier disabled: {}", e);
+                return;
+            }
+        };
+
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let from = since.max(now_secs() - NOTIFY_LOOKBACK_SECS);
+            for (realm, pool) in state.realms.all() {
+                if let Err(e) = notify_completed(&state, realm, &pool, from).await {
+                    tracing::warn!("Ticket notification failed for realm {}: {}", realm, e);
+                }
+            }
+        }
+    });
+}
+
+/// Emails the players whose tickets were completed since `from` and haven't
+/// been notified yet.
+async fn notify_completed(state: &AppState, realm: u32, pool: &sqlx::MySqlPool, from: i64) -> Result<(), String> {
+    let rows = sqlx::query("SELECT id, response FROM gm_ticket WHERE completed <> 0 AND lastModifiedTime >= ?")
+        .bind(from)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for row in rows {
+        let id = row.try_get::<u32, _>("id").unwrap_or_default();
+        let response = row.try_get::<String, _>("response").unwrap_or_default();
+        if claim_notification(state, realm, id).await.map_err(|e| e.to_string())? {
+            notify_player(state, pool, id, response.trim()).await;
+        }
+    }
+    Ok(())
+}
+
+/// Emails the owner of the ticket's character that a GM answered.
+/// Failures are logged; the in-game answer has already been delivered.
+async fn notify_player(state: &AppState, pool: &sqlx::MySqlPool, id: u32, answer: &str) {
+    let row = match sqlx::query("SELECT t.name, c.account FROM gm_ticket t JOIN characters c ON c.guid = t.playerGuid WHERE t.id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await {
+            Ok(Some(row)) => row,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Failed to look up owner of ticket {}: {}", id, e);
+                return;
+            }
+        };
+    let character = row.try_get::<String, _>("name").unwrap_or_default();
+    let account = row.try_get::<u32, _>("account").unwrap_or_default();
+
+    let email: Option<String> = match sqlx::query_scalar("SELECT email FROM account WHERE id = ?")
+        .bind(account)
+        .fetch_optional(&state.mysql_auth)
+        .await {
+            Ok(e) => e.filter(|e: &String| !e.is_empty()),
+            Err(e) => {
+                tracing::warn!("Failed to look up email for ticket {}: {}", id, e);
+                return;
+            }
+        };
+    let Some(email) = email else { return };
+
+    let body = format!(
+        "Hello!\n\nA Game Master answered the ticket opened by {}.\n\n{}\n\nYou can also check your tickets on the dashboard.\n\nSee you in Azeroth!",
+        character, answer
+    );
+    if let Err(e) = send_email(&email, "Your ticket has been answered", body).await {
+        tracing::warn!("Failed to send ticket {} notification: {}", id, e);
+    }
+}
+
+/// Tickets opened by the caller's characters on every realm, newest first.
+pub async fn list_my_tickets(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let claims = match authenticate(&headers) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    let accounts = match caller_game_accounts(&state, &claims).await {
+        Ok(a) => a,
+        Err(e) => return e.into_response(),
+    };
+    if accounts.is_empty() {
+        return Json(Vec::<GmTicket>::new()).into_response();
+    }
+
+    let mut tickets = Vec::new();
+    for (realm, pool) in state.realms.all() {
+        let mut builder: QueryBuilder<MySql> = QueryBuilder::new(TICKET_COLUMNS);
+        builder.push(" JOIN characters owner ON owner.guid = t.playerGuid WHERE owner.account IN (");
+        let mut separated = builder.separated(", ");
+        for account in &accounts {
+            separated.push_bind(*account);
+        }
+        builder.push(") ORDER BY t.createTime DESC LIMIT ").push_bind(MY_TICKETS_LIMIT);
+
+        match builder.build().fetch_all(&pool).await {
+            Ok(rows) => tickets.extend(rows.iter().map(|r| ticket_from_row(r, realm, false))),
+            Err(e) => {
+                tracing::error!("Failed to list player tickets on realm {}: {}", realm, e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+            }
+        }
+    }
+    tickets.sort_by_key(|t| std::cmp::Reverse(t.created_at));
+    tickets.truncate(MY_TICKETS_LIMIT as usize);
+
+    Json(tickets).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticket_status_prefers_the_ticket_type_then_progress() {
+        assert_eq!(ticket_status(TICKET_OPEN, 0, false, false), "open");
+        assert_eq!(ticket_status(TICKET_OPEN, 0, false, true), "assigned");
+        assert_eq!(ticket_status(TICKET_OPEN, 0, true, true), "answered");
+        // Closed by a GM or from the console (-1), whatever else was
+        // recorded.
+        assert_eq!(ticket_status(TICKET_OPEN, 5, true, true), "closed");
+        assert_eq!(ticket_status(TICKET_OPEN, -1, false, false), "closed");
+        assert_eq!(ticket_status(TICKET_CLOSED, 0, false, false), "closed");
+        assert_eq!(ticket_status(TICKET_CHARACTER_DELETED, 0, true, false), "deleted");
+    }
+}